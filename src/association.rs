@@ -0,0 +1,152 @@
+use crate::{package::Package, query::PackageQuery};
+use directories_next::BaseDirs;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{self, Path};
+
+/// A rule for opening the files under some path with a package, and optionally a profile,
+/// instead of the default package.
+///
+/// The pattern is a glob matched against the whole path of the file, where "*" and "?" stay
+/// within a directory, "**" crosses any number of them and a leading "~" is the home directory,
+/// like "~/projects/studio_a/**".
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AssociationRule {
+    pub pattern: String,
+    pub query: PackageQuery,
+    #[serde(default)]
+    pub profile: Option<String>,
+}
+
+impl AssociationRule {
+    pub fn matches(&self, file: &Path) -> bool {
+        // Relative paths come from the command line, so they're relative to where it was run.
+        let file = if file.has_root() {
+            file.to_path_buf()
+        } else {
+            path::absolute(file).unwrap_or_else(|_| file.to_path_buf())
+        };
+        glob_regex(&expand_home(&self.pattern)).is_match(&normalize(&file.to_string_lossy()))
+    }
+
+    /// The package to open the matching files with, out of the installed ones.
+    pub fn installed<'a>(
+        &self,
+        installed: impl IntoIterator<Item = &'a Package>,
+    ) -> Option<&'a Package> {
+        self.query.newest(installed)
+    }
+}
+
+/// The first of the rules that matches the file, along with its position.
+pub fn matching_rule<'a>(
+    rules: &'a [AssociationRule],
+    file: &Path,
+) -> Option<(usize, &'a AssociationRule)> {
+    rules
+        .iter()
+        .enumerate()
+        .find(|(_, rule)| rule.matches(file))
+}
+
+fn expand_home(pattern: &str) -> String {
+    match pattern.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => match BaseDirs::new() {
+            Some(base_dirs) => format!("{}{}", base_dirs.home_dir().display(), rest),
+            None => pattern.to_string(),
+        },
+        _ => pattern.to_string(),
+    }
+}
+
+/// Paths and patterns are compared with forward slashes, so either kind works on Windows.
+fn normalize(path: &str) -> String {
+    if cfg!(target_os = "windows") {
+        path.replace('\\', "/")
+    } else {
+        path.to_string()
+    }
+}
+
+fn glob_regex(pattern: &str) -> Regex {
+    // Windows' paths aren't case-sensitive.
+    let mut regex = String::from(if cfg!(target_os = "windows") {
+        "(?i)^"
+    } else {
+        "^"
+    });
+    let pattern = normalize(pattern);
+    let mut chars = pattern.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    // "a/**/b" also matches "a/b".
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+
+    Regex::new(&regex).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str) -> AssociationRule {
+        AssociationRule {
+            pattern: pattern.to_string(),
+            query: "lts".parse().unwrap(),
+            profile: None,
+        }
+    }
+
+    #[test]
+    fn match_globs() {
+        let file = Path::new("/projects/studio_a/shots/010/shot.blend");
+
+        assert!(rule("/projects/studio_a/**").matches(file));
+        assert!(rule("/projects/**/shot.blend").matches(file));
+        assert!(rule("/projects/studio_?/**/*.blend").matches(file));
+        assert!(rule("/projects/studio_a/shots/**/010/*.blend").matches(file));
+        assert!(!rule("/projects/studio_b/**").matches(file));
+        assert!(!rule("/projects/*/shot.blend").matches(file));
+        assert!(!rule("/projects/studio_a/*.blend").matches(file));
+        assert!(!rule("/projects/studio.a/**").matches(file));
+
+        if let Some(base_dirs) = BaseDirs::new() {
+            let file = base_dirs.home_dir().join("scenes").join("scene.blend");
+            assert!(rule("~/scenes/*.blend").matches(&file));
+        }
+    }
+
+    #[test]
+    fn first_match_wins() {
+        let rules = [
+            rule("/projects/studio_b/**"),
+            rule("/projects/studio_a/**"),
+            rule("/projects/**"),
+        ];
+
+        assert_eq!(
+            matching_rule(&rules, Path::new("/projects/studio_a/shot.blend")),
+            Some((1, &rules[1]))
+        );
+        assert_eq!(
+            matching_rule(&rules, Path::new("/projects/shot.blend")),
+            Some((2, &rules[2]))
+        );
+        assert_eq!(matching_rule(&rules, Path::new("/shot.blend")), None);
+    }
+}
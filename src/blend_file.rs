@@ -0,0 +1,324 @@
+use crate::{
+    error::{AblavemaError, Result},
+    package::{Build, Package},
+};
+use chrono::Local;
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    fs::{File, OpenOptions},
+    io::{copy, ErrorKind, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+use zstd::Decoder as ZstdDecoder;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Length of the longest header, the one introduced with Blender 5.0.
+const HEADER_LENGTH: usize = 17;
+
+/// The Blender version a .blend file was last saved with.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct BlendVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl BlendVersion {
+    fn of_package(package: &Package) -> Option<BlendVersion> {
+        Some(BlendVersion {
+            major: package.version.nth(0)?,
+            minor: package.version.nth(1)?,
+        })
+    }
+}
+
+impl fmt::Display for BlendVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// Read which version of Blender saved the file from its header, which is compressed along
+/// with the rest of the file when Blender's compression is enabled: gzip before 3.0 and
+/// Zstandard since.
+pub fn saved_with(path: &Path) -> Result<BlendVersion> {
+    let io_error = |e| AblavemaError::io(path, e);
+    let mut file = File::open(path).map_err(io_error)?;
+
+    let mut magic = [0; 4];
+    let read = file.read(&mut magic).map_err(io_error)?;
+    file.seek(SeekFrom::Start(0)).map_err(io_error)?;
+
+    let reader: Box<dyn Read> = if read >= 2 && magic[..2] == GZIP_MAGIC {
+        Box::new(GzDecoder::new(file))
+    } else if read == 4 && magic == ZSTD_MAGIC {
+        Box::new(ZstdDecoder::new(file).map_err(io_error)?)
+    } else {
+        Box::new(file)
+    };
+
+    let mut header = Vec::with_capacity(HEADER_LENGTH);
+    reader
+        .take(HEADER_LENGTH as u64)
+        .read_to_end(&mut header)
+        .map_err(io_error)?;
+
+    parse_header(&header).ok_or_else(|| AblavemaError::io(path, "not a .blend file"))
+}
+
+/// Headers are either "BLENDER-v306", with the pointer size, endianness and version, or since
+/// Blender 5.0 "BLENDER17-01v0500", with the header's length and format version before them.
+fn parse_header(header: &[u8]) -> Option<BlendVersion> {
+    let header = header.strip_prefix(b"BLENDER")?;
+    let number = |bytes: &[u8]| -> Option<u32> {
+        bytes
+            .iter()
+            .all(u8::is_ascii_digit)
+            .then(|| std::str::from_utf8(bytes).ok()?.parse().ok())
+            .flatten()
+    };
+
+    let version = match header {
+        [b'_' | b'-', b'v' | b'V', version @ ..] if version.len() >= 3 => number(&version[..3])?,
+        [length, _, b'-', _, _, b'v' | b'V', version @ ..]
+            if length.is_ascii_digit() && version.len() >= 4 =>
+        {
+            number(&version[..4])?
+        }
+        _ => return None,
+    };
+
+    Some(BlendVersion {
+        major: version / 100,
+        minor: version % 100,
+    })
+}
+
+/// The package that fits a file saved with `version` best: the newest one of the same series,
+/// otherwise the oldest newer one, which can still read it, and as a last resort the newest
+/// older one.
+pub fn closest_package<'a>(
+    version: BlendVersion,
+    packages: impl IntoIterator<Item = &'a Package>,
+) -> Option<&'a Package> {
+    let mut same = None;
+    let mut newer: Option<(BlendVersion, &Package)> = None;
+    let mut older: Option<(BlendVersion, &Package)> = None;
+
+    for package in packages.into_iter().filter(|package| package.runs_here()) {
+        let package_version = match BlendVersion::of_package(package) {
+            Some(package_version) => package_version,
+            None => continue,
+        };
+
+        if package_version == version {
+            if same.is_none_or(|same: &Package| package.version > same.version) {
+                same = Some(package);
+            }
+        } else if package_version > version {
+            if newer.is_none_or(|(newer, _)| package_version < newer) {
+                newer = Some((package_version, package));
+            }
+        } else if older.is_none_or(|(older, _)| package_version > older) {
+            older = Some((package_version, package));
+        }
+    }
+
+    same.or(newer.map(|(_, package)| package))
+        .or(older.map(|(_, package)| package))
+}
+
+/// Why saving a file that was saved with `version` may lose data once it's opened with `package`,
+/// which is when the package is older than the file or a development build newer than it.
+pub fn opening_risk(version: BlendVersion, package: &Package) -> Option<String> {
+    let package_version = BlendVersion::of_package(package)?;
+
+    if package_version < version {
+        Some(format!(
+            "The file was saved with Blender {}, but '{}' is Blender {}. Saving it with an \
+            older version loses whatever that version doesn't know about.",
+            version, package.name, package_version
+        ))
+    } else if package_version > version
+        && matches!(
+            package.build,
+            Build::DailyLatest(_)
+                | Build::DailyArchive(_)
+                | Build::ExperimentalLatest(_)
+                | Build::ExperimentalArchive(_)
+                | Build::PatchLatest(_)
+                | Build::PatchArchive(_)
+        )
+    {
+        Some(format!(
+            "The file was saved with Blender {}, but '{}' is a development build of Blender {}. \
+            Once saved with it, the file may not open properly in Blender {} anymore.",
+            version, package.name, package_version, version
+        ))
+    } else {
+        None
+    }
+}
+
+/// Copy the file next to itself with the current time in its name, like
+/// "scene_2024-06-01_12-30-00.blend", followed by a counter like "_2" when there's already a
+/// backup from the same second.
+pub fn back_up(file: &Path) -> Result<PathBuf> {
+    let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+    let time = Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
+    let mut source = File::open(file).map_err(|e| AblavemaError::io(file, e))?;
+
+    for count in 1.. {
+        let mut name = match count {
+            1 => format!("{}_{}", stem, time),
+            _ => format!("{}_{}_{}", stem, time, count),
+        };
+        if let Some(extension) = file.extension() {
+            name.push('.');
+            name.push_str(&extension.to_string_lossy());
+        }
+        let backup = file.with_file_name(name);
+
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&backup)
+        {
+            Ok(mut destination) => {
+                copy(&mut source, &mut destination).map_err(|e| AblavemaError::io(&backup, e))?;
+                return Ok(backup);
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(AblavemaError::io(&backup, e)),
+        }
+    }
+
+    unreachable!("ran out of backup names")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        package::Build,
+        test_helpers::{package, scratch_dir},
+    };
+    use flate2::{write::GzEncoder, Compression};
+    use std::{fs::remove_dir_all, io::Write};
+
+    fn version(major: u32, minor: u32) -> BlendVersion {
+        BlendVersion { major, minor }
+    }
+
+    #[test]
+    fn parse_headers() {
+        assert_eq!(parse_header(b"BLENDER-v306RENDH"), Some(version(3, 6)));
+        assert_eq!(parse_header(b"BLENDER_V279REND"), Some(version(2, 79)));
+        assert_eq!(parse_header(b"BLENDER17-01v0500"), Some(version(5, 0)));
+        assert_eq!(parse_header(b"BLENDER-v3"), None);
+        assert_eq!(parse_header(b"BLENDER-vabc"), None);
+        assert_eq!(parse_header(b"PK\x03\x04"), None);
+    }
+
+    #[test]
+    fn read_compressed_files() {
+        let dir = scratch_dir("compressed");
+        let path = dir.join("scene.blend");
+
+        let mut encoder = GzEncoder::new(File::create(&path).unwrap(), Compression::default());
+        encoder.write_all(b"BLENDER-v293REND").unwrap();
+        encoder.finish().unwrap();
+        assert_eq!(saved_with(&path).unwrap(), version(2, 93));
+
+        let mut encoder = zstd::Encoder::new(File::create(&path).unwrap(), 3).unwrap();
+        encoder.write_all(b"BLENDER-v401REND").unwrap();
+        encoder.finish().unwrap();
+        assert_eq!(saved_with(&path).unwrap(), version(4, 1));
+
+        std::fs::write(&path, b"BLENDER17-01v0501").unwrap();
+        assert_eq!(saved_with(&path).unwrap(), version(5, 1));
+
+        std::fs::write(&path, b"BLEN").unwrap();
+        assert!(saved_with(&path).is_err());
+
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn closest() {
+        let packages = [
+            package("a", "2.93.18"),
+            package("b", "3.6.2"),
+            package("c", "3.6.5"),
+            package("d", "4.2.0"),
+            package("e", "4.1.1"),
+        ];
+        let name = |version| closest_package(version, &packages).map(|p| p.name.as_str());
+
+        assert_eq!(name(version(3, 6)), Some("c"));
+        assert_eq!(name(version(4, 0)), Some("e"));
+        assert_eq!(name(version(2, 79)), Some("a"));
+        assert_eq!(name(version(5, 0)), Some("d"));
+        assert_eq!(closest_package(version(3, 6), &[]), None);
+    }
+
+    #[test]
+    fn risky_openings() {
+        let stable = package("stable", "3.6.5");
+        assert!(opening_risk(version(4, 2), &stable).is_some());
+        assert!(opening_risk(version(3, 6), &stable).is_none());
+        assert!(opening_risk(version(2, 93), &stable).is_none());
+
+        let daily = Package {
+            build: Build::DailyLatest(String::from("Alpha")),
+            ..package("daily", "4.3.0")
+        };
+        assert!(opening_risk(version(4, 2), &daily).is_some());
+        assert!(opening_risk(version(4, 3), &daily).is_none());
+        assert!(opening_risk(version(4, 4), &daily).is_some());
+    }
+
+    #[test]
+    fn back_up_next_to_file() {
+        let dir = scratch_dir("backup");
+        let file = dir.join("scene.blend");
+        std::fs::write(&file, b"BLENDER-v306").unwrap();
+
+        let backup = back_up(&file).unwrap();
+        assert_eq!(backup.parent(), Some(dir.as_path()));
+        let name = backup.file_name().unwrap().to_string_lossy().to_string();
+        assert!(
+            name.starts_with("scene_") && name.ends_with(".blend"),
+            "{}",
+            name
+        );
+        assert_eq!(std::fs::read(&backup).unwrap(), b"BLENDER-v306");
+
+        // Backups made within the same second don't replace each other.
+        std::fs::write(&file, b"BLENDER-v401").unwrap();
+        let backups = [back_up(&file).unwrap(), back_up(&file).unwrap()];
+        assert_ne!(backups[0], backup);
+        assert_ne!(backups[0], backups[1]);
+        assert_eq!(std::fs::read(&backup).unwrap(), b"BLENDER-v306");
+        assert_eq!(std::fs::read(&backups[1]).unwrap(), b"BLENDER-v401");
+
+        let file = dir.join("scene");
+        std::fs::write(&file, b"BLENDER-v306").unwrap();
+        let name = back_up(&file)
+            .unwrap()
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        assert!(
+            name.starts_with("scene_") && !name.contains('.'),
+            "{}",
+            name
+        );
+
+        remove_dir_all(dir).unwrap();
+    }
+}
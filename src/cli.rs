@@ -1,24 +1,168 @@
 use crate::{
     gui::extra::GuiFlags,
-    helpers::is_time_to_update,
-    releases::Releases,
+    helpers::{format_bytes, is_time_to_update},
+    installer::{install, Progress, Source},
+    package::{Package, PackageState},
+    query::{PackageQuery, Resolved},
+    releases::{
+        daily_archive::DailyArchive, daily_latest::DailyLatest,
+        experimental_archive::ExperimentalArchive, experimental_latest::ExperimentalLatest,
+        found_new_packages, lts::Lts, patch_archive::PatchArchive, patch_latest::PatchLatest,
+        stable_archive::StableArchive, stable_latest::StableLatest, ReleaseType, Releases,
+    },
     self_updater::SelfUpdater,
-    settings::{get_setting, CAN_CONNECT, LAUNCH_GUI},
+    settings::{get_setting, save_settings, set_setting, CAN_CONNECT, LAUNCH_GUI},
 };
-use clap::{command, Arg};
+use clap::{command, Arg, ArgMatches, Command};
 use device_query::{DeviceQuery, DeviceState};
-use std::sync::atomic::Ordering;
+use std::{
+    io::{stdout, Write},
+    path::PathBuf,
+    process::exit,
+    sync::atomic::Ordering,
+};
+use tokio::sync::mpsc::unbounded_channel;
+
+const BUILD_TYPES: [&str; 10] = [
+    "all",
+    "daily-latest",
+    "daily-archive",
+    "experimental-latest",
+    "experimental-archive",
+    "patch-latest",
+    "patch-archive",
+    "stable-latest",
+    "stable-archive",
+    "lts",
+];
+
+macro_rules! fetch_release {
+    ($releases:expr, $field:ident, $release:ty, $label:expr) => {{
+        println!("Fetching {}...", $label);
+        // Counted out of the fetched packages that weren't known before.
+        let result = match <$release>::get_new_packages(&$releases.$field).await {
+            Ok(Some(new_packages)) => {
+                let count = new_packages.len();
+                $releases.$field.add_new_packages(new_packages);
+                $releases.$field.save().map(|_| count)
+            }
+            Ok(None) => Ok(0),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(count) => println!(
+                "Found {} new package{} for {}.",
+                count,
+                if count == 1 { "" } else { "s" },
+                $label
+            ),
+            Err(e) => eprintln!("Failed to fetch {}: {}", $label, e),
+        }
+    }};
+}
 
 pub async fn run_cli() -> GuiFlags {
     let args = command!()
+        .args_conflicts_with_subcommands(true)
         .arg(
             Arg::new("path")
                 .value_name("PATH")
                 .help("Path to .blend file"),
         )
+        .subcommand(
+            Command::new("list")
+                .about("List the known packages")
+                .arg(
+                    Arg::new("query")
+                        .value_name("QUERY")
+                        .help("Only list the packages matching a query like \"3.6\" or \"lts\""),
+                )
+                .arg(
+                    Arg::new("installed")
+                        .short('i')
+                        .long("installed")
+                        .help("Only list installed packages"),
+                ),
+        )
+        .subcommand(
+            Command::new("install")
+                .about("Download and install a package")
+                .arg(
+                    Arg::new("query")
+                        .value_name("QUERY")
+                        .help(
+                            "Name of the package, as shown by the list command, or a query like \
+                            \"3.6\", \"4.2.*\", \"lts\", \"latest-stable\" or \"daily:main\" \
+                            for the newest match",
+                        )
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("from-cache")
+                        .short('c')
+                        .long("from-cache")
+                        .help("Reuse the previously downloaded archive"),
+                ),
+        )
+        .subcommand(
+            Command::new("remove")
+                .about("Remove an installed package")
+                .arg(
+                    Arg::new("name")
+                        .value_name("NAME")
+                        .help("Name of the package, as shown by the list command")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("register")
+                .about("Register a Blender that wasn't installed by Ablavema")
+                .arg(
+                    Arg::new("name")
+                        .value_name("NAME")
+                        .help("Unique name for the package")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("version")
+                        .value_name("VERSION")
+                        .help("Version of Blender, like 3.2.0")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("path")
+                        .value_name("PATH")
+                        .help("Directory containing the executable, or the executable itself")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("label")
+                        .short('l')
+                        .long("label")
+                        .value_name("LABEL")
+                        .takes_value(true)
+                        .help("What the package is for"),
+                ),
+        )
+        .subcommand(
+            Command::new("fetch")
+                .about("Fetch new packages of a build type")
+                .arg(
+                    Arg::new("build-type")
+                        .value_name("BUILD_TYPE")
+                        .possible_values(BUILD_TYPES)
+                        .required(true),
+                ),
+        )
         .get_matches();
 
     let mut releases = Releases::init().await;
+
+    if let Some(subcommand) = args.subcommand() {
+        run_subcommand(subcommand, releases).await;
+        exit(0);
+    }
+
     let mut self_releases = None;
 
     if get_setting().check_updates_at_launch {
@@ -28,9 +172,14 @@ pub async fn run_cli() -> GuiFlags {
 
                 // This only launches the GUI when new packages were found for the first time.
                 // Meaning it won't pop the GUI again if the user chose to ignore them.
-                if packages.0 {
+                if found_new_packages(&packages.0) {
                     LAUNCH_GUI.store(true, Ordering::Relaxed);
                 }
+                for (name, result) in &packages.0 {
+                    if let Err(e) = result {
+                        eprintln!("Failed to check {} for updates: {}", name, e);
+                    }
+                }
 
                 releases.add_new_packages(packages);
             } else {
@@ -77,3 +226,231 @@ pub async fn run_cli() -> GuiFlags {
         self_releases,
     }
 }
+
+/// Parse the query given to a subcommand, exiting on invalid ones.
+fn parse_query(args: &ArgMatches) -> Option<PackageQuery> {
+    args.value_of("query").map(|query| match query.parse() {
+        Ok(query) => query,
+        Err(e) => {
+            eprintln!("Invalid query '{}': {}.", query, e);
+            exit(1);
+        }
+    })
+}
+
+/// Handle the headless subcommands, which never launch the GUI.
+async fn run_subcommand(subcommand: (&str, &ArgMatches), mut releases: Releases) {
+    match subcommand {
+        ("list", args) => {
+            let only_installed = args.is_present("installed");
+            let query = parse_query(args);
+            let mut packages = releases.build_vec();
+            packages.sort();
+
+            for package in packages.iter().filter(|package| {
+                (!only_installed || matches!(package.state, PackageState::Installed))
+                    && query.as_ref().is_none_or(|query| query.matches(package))
+            }) {
+                println!(
+                    "{:<50} {:<14} {}{}{}",
+                    package.name,
+                    package.version.to_string(),
+                    package.build_type,
+                    if package.runs_here() {
+                        String::new()
+                    } else {
+                        format!(" [{}]", package.target())
+                    },
+                    if matches!(package.state, PackageState::Installed) {
+                        " [installed]"
+                    } else if package.cached {
+                        " [cached]"
+                    } else {
+                        ""
+                    }
+                );
+            }
+        }
+        ("install", args) => {
+            let query = parse_query(args).unwrap();
+            let packages = releases.build_vec();
+
+            let package = match query.resolve(releases.installed.iter(), &packages) {
+                Some(Resolved::Installable(package)) => package.clone(),
+                Some(Resolved::Installed(package)) => {
+                    println!("Package '{}' is already installed.", package.name);
+                    return;
+                }
+                None => {
+                    eprintln!(
+                        "No package matches '{}'. Try fetching its build type first.",
+                        query
+                    );
+                    exit(1);
+                }
+            };
+            let name = package.name.clone();
+
+            let source = if args.is_present("from-cache") {
+                if package.cached_archive().is_none() {
+                    eprintln!("Package '{}' has no archive in the cache.", name);
+                    exit(1);
+                }
+
+                Source::Cache
+            } else {
+                if !CAN_CONNECT.load(Ordering::Relaxed) {
+                    eprintln!("Failed to connect to server and install '{}'.", name);
+                    exit(1);
+                }
+
+                Source::Download
+            };
+
+            let (sender, mut receiver) = unbounded_channel();
+            tokio::spawn(install(package, source, sender));
+            let mut last_percentage = -1;
+
+            while let Some(progress) = receiver.recv().await {
+                match progress {
+                    Progress::Started => println!("Downloading '{}'...", name),
+                    Progress::DownloadProgress(status) => {
+                        if status.percentage() as i32 != last_percentage {
+                            last_percentage = status.percentage() as i32;
+                            // Clears the rest of the line, since the rate and ETA can make it shorter.
+                            print!("\r{:>3}% ({})\x1b[K", last_percentage, status);
+                            let _ = stdout().flush();
+                        }
+                    }
+                    Progress::ExtractionProgress { extracted, total } => {
+                        let percentage = (extracted * 100).checked_div(total).unwrap_or(0) as i32;
+                        if percentage != last_percentage {
+                            last_percentage = percentage;
+                            print!(
+                                "\r{:>3}% ({} of {})",
+                                percentage,
+                                format_bytes(extracted),
+                                format_bytes(total)
+                            );
+                            let _ = stdout().flush();
+                        }
+                    }
+                    Progress::FinishedDownloading => {
+                        last_percentage = -1;
+                        println!("\nExtracting '{}'...", name);
+                    }
+                    Progress::FinishedExtracting => println!(),
+                    Progress::FinishedInstalling => println!("Installed: {}", name),
+                    Progress::Errored(message) => {
+                        eprintln!("\nFailed to install '{}': {}.", name, message);
+                        exit(1);
+                    }
+                }
+            }
+
+            releases.installed.fetch();
+            releases.installed.update_default();
+        }
+        ("remove", args) => {
+            let name = args.value_of("name").unwrap();
+
+            match releases
+                .installed
+                .iter()
+                .find(|package| package.name == name)
+            {
+                Some(package) => {
+                    package.remove();
+
+                    let default_package_option = get_setting().default_package.clone();
+                    if let Some(default_package) = default_package_option {
+                        if default_package == *package {
+                            set_setting().default_package = None;
+                            save_settings();
+                        }
+                    }
+                }
+                None => {
+                    eprintln!("Package '{}' is not installed.", name);
+                    // Queries aren't accepted, so that a loose one can't remove a package by
+                    // surprise, but the package it would pick is worth pointing out.
+                    if let Some(package) = name
+                        .parse::<PackageQuery>()
+                        .ok()
+                        .and_then(|query| query.newest(releases.installed.iter()))
+                    {
+                        eprintln!("Did you mean '{}'?", package.name);
+                    }
+                    exit(1);
+                }
+            }
+        }
+        ("register", args) => {
+            let package = match Package::new_custom(
+                args.value_of("name").unwrap(),
+                args.value_of("version").unwrap(),
+                args.value_of("label").unwrap_or_default(),
+                PathBuf::from(args.value_of("path").unwrap()),
+            ) {
+                Ok(package) => package,
+                Err(message) => {
+                    eprintln!("Failed to register package: {}.", message);
+                    exit(1);
+                }
+            };
+
+            if let Err(message) = releases.register_custom_package(package) {
+                eprintln!("Failed to register package: {}.", message);
+                exit(1);
+            }
+        }
+        ("fetch", args) => {
+            if !CAN_CONNECT.load(Ordering::Relaxed) {
+                eprintln!("Failed to connect to server and fetch packages.");
+                exit(1);
+            }
+
+            let build_type = args.value_of("build-type").unwrap();
+            let all = build_type == "all";
+
+            if all || build_type == "daily-latest" {
+                fetch_release!(releases, daily_latest, DailyLatest, "Daily (latest)");
+            }
+            if all || build_type == "daily-archive" {
+                fetch_release!(releases, daily_archive, DailyArchive, "Daily (archive)");
+            }
+            if all || build_type == "experimental-latest" {
+                fetch_release!(
+                    releases,
+                    experimental_latest,
+                    ExperimentalLatest,
+                    "Experimental (latest)"
+                );
+            }
+            if all || build_type == "experimental-archive" {
+                fetch_release!(
+                    releases,
+                    experimental_archive,
+                    ExperimentalArchive,
+                    "Experimental (archive)"
+                );
+            }
+            if all || build_type == "patch-latest" {
+                fetch_release!(releases, patch_latest, PatchLatest, "Patch (latest)");
+            }
+            if all || build_type == "patch-archive" {
+                fetch_release!(releases, patch_archive, PatchArchive, "Patch (archive)");
+            }
+            if all || build_type == "stable-latest" {
+                fetch_release!(releases, stable_latest, StableLatest, "Stable (latest)");
+            }
+            if all || build_type == "stable-archive" {
+                fetch_release!(releases, stable_archive, StableArchive, "Stable (archive)");
+            }
+            if all || build_type == "lts" {
+                fetch_release!(releases, lts, Lts, "Long-term Support");
+            }
+        }
+        _ => unreachable!("Unknown subcommand"),
+    }
+}
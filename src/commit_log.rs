@@ -0,0 +1,244 @@
+use std::{path::Path, process::Command};
+
+/// Separates the fields of a commit in the output of `git log`.
+const FIELD_SEPARATOR: char = '\x1f';
+/// Separates the commits themselves in the output of `git log`.
+const COMMIT_SEPARATOR: char = '\x1e';
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Commit {
+    pub hash: String,
+    pub author: String,
+    pub date: String,
+    pub title: String,
+    pub body: String,
+}
+
+impl Commit {
+    pub fn url(&self) -> String {
+        format!(
+            "https://projects.blender.org/blender/blender/commit/{}",
+            self.hash
+        )
+    }
+}
+
+/// The commits between the builds of two packages, which is what changed from one to the other.
+#[derive(Clone, Debug)]
+pub struct CommitLog {
+    pub from: String,
+    pub to: String,
+    pub commits: Result<Vec<Commit>, String>,
+}
+
+fn git(repository: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repository)
+        .args(args)
+        .output()
+        .map_err(|e| format!("couldn't run git: {}", e))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+fn is_hash(hash: &str) -> bool {
+    (7..=40).contains(&hash.len())
+        && hash
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+}
+
+fn is_ancestor(repository: &Path, ancestor: &str, descendant: &str) -> bool {
+    git(
+        repository,
+        &["merge-base", "--is-ancestor", ancestor, descendant],
+    )
+    .is_ok()
+}
+
+/// Get the commits between two hashes from a local clone of Blender's repository, newest first.
+/// The order of the hashes doesn't matter, and if neither is an ancestor of the other, like
+/// builds from different branches, the commits that are in only one of them are returned.
+pub fn commits_between(repository: &Path, a: &str, b: &str) -> Result<Vec<Commit>, String> {
+    // Bare clones work just as well, which don't have a ".git" directory.
+    if git(repository, &["rev-parse", "--git-dir"]).is_err() {
+        return Err(format!("'{}' isn't a git repository", repository.display()));
+    }
+
+    // Hashes come from package names, so anything else must not reach git as an argument.
+    for hash in [a, b] {
+        if !is_hash(hash) {
+            return Err(format!("'{}' isn't a commit hash", hash));
+        }
+    }
+
+    for hash in [a, b] {
+        if git(
+            repository,
+            &["cat-file", "-e", &format!("{}^{{commit}}", hash)],
+        )
+        .is_err()
+        {
+            return Err(format!(
+                "commit '{}' isn't in the repository, try pulling the latest changes",
+                hash
+            ));
+        }
+    }
+
+    let range = if is_ancestor(repository, a, b) {
+        format!("{}..{}", a, b)
+    } else if is_ancestor(repository, b, a) {
+        format!("{}..{}", b, a)
+    } else {
+        format!("{}...{}", a, b)
+    };
+
+    let output = git(
+        repository,
+        &[
+            "log",
+            "--date=short",
+            "--format=%H%x1f%an%x1f%ad%x1f%s%x1f%b%x1e",
+            &range,
+        ],
+    )?;
+
+    Ok(output
+        .split(COMMIT_SEPARATOR)
+        .filter_map(|commit| {
+            let mut fields = commit.trim_start().splitn(5, FIELD_SEPARATOR);
+            Some(Commit {
+                hash: fields.next().filter(|hash| !hash.is_empty())?.to_string(),
+                author: fields.next()?.to_string(),
+                date: fields.next()?.to_string(),
+                title: fields.next()?.to_string(),
+                body: fields.next()?.trim().to_string(),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::scratch_dir;
+    use std::{env::temp_dir, fs::remove_dir_all, path::PathBuf};
+
+    /// A throwaway repository with a main branch of three commits and a side branch of one,
+    /// which returns the hashes in that order.
+    fn fixture(name: &str) -> (PathBuf, Vec<String>) {
+        let repository = scratch_dir(name);
+        let run = |args: &[&str]| {
+            let output = Command::new("git")
+                .arg("-C")
+                .arg(&repository)
+                .args([
+                    "-c",
+                    "user.name=Tester",
+                    "-c",
+                    "user.email=tester@example.com",
+                ])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "{:?}", output);
+            String::from_utf8(output.stdout).unwrap().trim().to_string()
+        };
+
+        Command::new("git")
+            .args(["init", "-q"])
+            .arg(&repository)
+            .status()
+            .unwrap();
+        let mut hashes = Vec::new();
+        for (title, body) in [
+            ("Initial commit", ""),
+            (
+                "Fix crash on startup",
+                "It happened when no file was given.",
+            ),
+            ("Add feature", "First line.\n\nSecond paragraph."),
+        ] {
+            run(&["commit", "-q", "--allow-empty", "-m", title, "-m", body]);
+            hashes.push(run(&["rev-parse", "HEAD"]));
+        }
+        run(&["checkout", "-q", "-b", "side", &hashes[1]]);
+        run(&["commit", "-q", "--allow-empty", "-m", "Side change"]);
+        hashes.push(run(&["rev-parse", "HEAD"]));
+
+        (repository, hashes)
+    }
+
+    #[test]
+    fn commits_between_in_either_order() {
+        let (repository, hashes) = fixture("either-order");
+
+        let commits = commits_between(&repository, &hashes[0], &hashes[2]).unwrap();
+        assert_eq!(
+            commits.iter().map(|c| c.title.as_str()).collect::<Vec<_>>(),
+            ["Add feature", "Fix crash on startup"]
+        );
+        assert_eq!(commits[0].body, "First line.\n\nSecond paragraph.");
+        assert_eq!(commits[1].body, "It happened when no file was given.");
+        assert_eq!(commits[1].author, "Tester");
+        assert_eq!(commits[0].hash, hashes[2]);
+
+        let reversed = commits_between(&repository, &hashes[2][..12], &hashes[0][..12]).unwrap();
+        assert_eq!(reversed, commits);
+
+        remove_dir_all(repository).unwrap();
+    }
+
+    #[test]
+    fn commits_between_branches() {
+        let (repository, hashes) = fixture("branches");
+
+        let commits = commits_between(&repository, &hashes[2], &hashes[3]).unwrap();
+        let mut titles = commits.iter().map(|c| c.title.as_str()).collect::<Vec<_>>();
+        titles.sort_unstable();
+        assert_eq!(titles, ["Add feature", "Side change"]);
+
+        remove_dir_all(repository).unwrap();
+    }
+
+    #[test]
+    fn commits_between_unknown_commit() {
+        let (repository, hashes) = fixture("unknown");
+
+        let error = commits_between(&repository, &hashes[0], "0123456789ab").unwrap_err();
+        assert!(error.contains("'0123456789ab' isn't in the repository"));
+        assert!(commits_between(&temp_dir(), &hashes[0], &hashes[1]).is_err());
+
+        for hash in ["--output=/tmp/log", "main", "0123ab", "0123456789AB"] {
+            let error = commits_between(&repository, &hashes[0], hash).unwrap_err();
+            assert!(error.contains("isn't a commit hash"), "{}", error);
+        }
+
+        remove_dir_all(repository).unwrap();
+    }
+
+    #[test]
+    fn commits_between_in_bare_clone() {
+        let (repository, hashes) = fixture("bare-source");
+        let bare = scratch_dir("bare");
+        let status = Command::new("git")
+            .args(["clone", "-q", "--bare", "--no-local"])
+            .arg(&repository)
+            .arg(&bare)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let commits = commits_between(&bare, &hashes[0], &hashes[1]).unwrap();
+        assert_eq!(commits[0].title, "Fix crash on startup");
+
+        remove_dir_all(repository).unwrap();
+        remove_dir_all(bare).unwrap();
+    }
+}
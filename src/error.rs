@@ -0,0 +1,118 @@
+use std::{fmt, path::PathBuf, time::SystemTime};
+
+pub type Result<T, E = AblavemaError> = std::result::Result<T, E>;
+
+/// Everything that can go wrong while fetching, storing and installing packages.
+/// The causes are kept as text so the error can travel inside GUI messages.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AblavemaError {
+    /// A request that didn't go through or came back with an error status.
+    Network {
+        url: String,
+        message: String,
+    },
+    /// A server that asked to slow down or temporarily banned us, which isn't contacted again
+    /// until the backoff is over.
+    RateLimited {
+        host: String,
+        until: SystemTime,
+    },
+    /// A page that isn't laid out the way the scraper expects, which usually means it changed.
+    Parse {
+        url: String,
+        message: String,
+    },
+    Io {
+        path: PathBuf,
+        message: String,
+    },
+    /// A database file that couldn't be read or written.
+    Database {
+        path: PathBuf,
+        message: String,
+    },
+}
+
+impl AblavemaError {
+    pub fn parse(url: &str, message: impl Into<String>) -> Self {
+        AblavemaError::Parse {
+            url: url.to_string(),
+            message: message.into(),
+        }
+    }
+
+    pub fn io(path: impl Into<PathBuf>, error: impl fmt::Display) -> Self {
+        AblavemaError::Io {
+            path: path.into(),
+            message: error.to_string(),
+        }
+    }
+
+    pub fn database(path: impl Into<PathBuf>, error: impl fmt::Display) -> Self {
+        AblavemaError::Database {
+            path: path.into(),
+            message: error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for AblavemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AblavemaError::Network { url, message } => {
+                write!(f, "couldn't fetch '{}': {}", url, message)
+            }
+            AblavemaError::RateLimited { host, until } => {
+                let minutes = until
+                    .duration_since(SystemTime::now())
+                    .map(|remaining| remaining.as_secs().div_ceil(60))
+                    .unwrap_or_default()
+                    .max(1);
+                write!(
+                    f,
+                    "rate limited by {}, retry in {} minute{}",
+                    host,
+                    minutes,
+                    if minutes == 1 { "" } else { "s" }
+                )
+            }
+            AblavemaError::Parse { url, message } => {
+                write!(f, "unexpected page layout at '{}': {}", url, message)
+            }
+            AblavemaError::Io { path, message } => {
+                write!(f, "'{}': {}", path.display(), message)
+            }
+            AblavemaError::Database { path, message } => {
+                write!(f, "database '{}': {}", path.display(), message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AblavemaError {}
+
+impl From<reqwest::Error> for AblavemaError {
+    fn from(error: reqwest::Error) -> Self {
+        AblavemaError::Network {
+            url: error.url().map(|url| url.to_string()).unwrap_or_default(),
+            message: error.without_url().to_string(),
+        }
+    }
+}
+
+/// Turns what a scraper failed to find into a parse error about the page it was looking at.
+pub trait OrParseError<T> {
+    fn or_parse_error(self, url: &str, message: &str) -> Result<T>;
+}
+
+impl<T> OrParseError<T> for Option<T> {
+    fn or_parse_error(self, url: &str, message: &str) -> Result<T> {
+        self.ok_or_else(|| AblavemaError::parse(url, message))
+    }
+}
+
+impl<T, E: fmt::Display> OrParseError<T> for std::result::Result<T, E> {
+    fn or_parse_error(self, url: &str, message: &str) -> Result<T> {
+        self.map_err(|e| AblavemaError::parse(url, format!("{} ({})", message, e)))
+    }
+}
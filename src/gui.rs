@@ -4,28 +4,40 @@ pub mod filters;
 mod install;
 mod message;
 mod package;
+mod render;
 pub mod sort_by;
 pub mod style;
 pub mod tabs;
 use self::{
     controls::Controls,
-    extra::{DiskSpace, GlobalTokio, GuiFlags, GuiState},
+    extra::{ActiveRender, BlendFile, BlenderConfig, DiskSpace, GlobalTokio, GuiFlags, GuiState},
     install::Install,
     message::GuiMessage,
+    render::Render,
     tabs::recent_files::RecentFile,
 };
 use crate::{
+    blend_file::{closest_package, saved_with, BlendVersion},
+    commit_log::{commits_between, CommitLog},
+    error::{AblavemaError, Result},
     gui::tabs::Tab,
     helpers::check_connection,
-    package::Package,
+    http,
+    installer::{DownloadState, QueuedDownload},
+    mirrors,
+    package::{Package, PackageState},
+    profiles::{find_blender_configs, Profile},
     releases::{
         daily_archive::DailyArchive, daily_latest::DailyLatest,
         experimental_archive::ExperimentalArchive, experimental_latest::ExperimentalLatest,
         lts::Lts, patch_archive::PatchArchive, patch_latest::PatchLatest,
-        stable_archive::StableArchive, stable_latest::StableLatest, ReleaseType, Releases,
+        stable_archive::StableArchive, stable_latest::StableLatest, CheckResults, ReleaseType,
+        Releases,
     },
+    renderer::RenderJob,
     self_updater::SelfUpdater,
     settings::{get_setting, save_settings, set_setting, CAN_CONNECT},
+    version_pin::VersionPin,
 };
 use fs2::available_space;
 use fs_extra::dir;
@@ -37,19 +49,20 @@ use iced::{
     },
     Command, Length, Space, Subscription,
 };
+use itertools::Itertools;
 use self_update::update::Release;
-use std::sync::atomic::Ordering;
+use std::{path::Path, sync::atomic::Ordering};
 use tokio::task::spawn_blocking;
 
 macro_rules! build_fetching {
     ($name:ident, $release:ident) => {
-        async fn $name(packages: $release) -> (bool, $release) {
+        async fn $name(packages: $release) -> (Result<bool>, $release) {
             check_connection().await;
 
             if CAN_CONNECT.load(Ordering::Relaxed) {
                 $release::check_updates(packages).await
             } else {
-                (false, packages)
+                (Ok(false), packages)
             }
         }
     };
@@ -59,9 +72,21 @@ macro_rules! build_fetching {
 pub struct Gui {
     releases: Releases,
     packages: Vec<Package>,
-    installing: Vec<Package>,
+    download_queue: Vec<QueuedDownload>,
     file_path: Option<String>,
+    /// Version of Blender that saved the file at `file_path`, if it could be read.
+    file_version: Option<BlendVersion>,
+    /// Package the project of the file at `file_path` is pinned to, see `VersionPin`.
+    file_pin: Option<VersionPin>,
     recent_files: Vec<RecentFile>,
+    profiles: Vec<Profile>,
+    render_queue: Vec<RenderJob>,
+    render: Option<ActiveRender>,
+    render_queue_running: bool,
+    compare_from: Option<Package>,
+    commit_log: Option<CommitLog>,
+    /// Sources that failed the last time they were checked, until they're checked successfully.
+    fetch_errors: Vec<(&'static str, AblavemaError)>,
     state: GuiState,
     controls: Controls,
     self_releases: Option<Vec<Release>>,
@@ -70,7 +95,38 @@ pub struct Gui {
 impl Gui {
     pub fn sync(&mut self) {
         self.releases.sync();
-        self.packages = self.releases.build_vec();
+        let old_packages = std::mem::replace(&mut self.packages, self.releases.build_vec());
+
+        // Keep the changelogs that were expanded before the packages got rebuilt.
+        for package in self.packages.iter_mut() {
+            if let Some(old_package) = old_packages
+                .iter()
+                .find(|old_package| old_package.name == package.name)
+            {
+                package.changelog_view = old_package.changelog_view;
+
+                // Neither are installations in progress known to the releases.
+                if matches!(
+                    old_package.state,
+                    PackageState::Downloading { .. } | PackageState::Extracting { .. }
+                ) {
+                    package.state = old_package.state.clone();
+                }
+            }
+        }
+
+        self.refresh_download_states();
+    }
+
+    /// Keep the errors of the sources that failed and forget those of the ones that worked.
+    fn record_checks(&mut self, results: CheckResults) {
+        for (name, result) in results {
+            self.fetch_errors.retain(|(failed, _)| *failed != name);
+            if let Err(e) = result {
+                eprintln!("Failed to check {} for new packages: {}", name, e);
+                self.fetch_errors.push((name, e));
+            }
+        }
     }
 
     /// A tuple is returned where:
@@ -79,18 +135,28 @@ impl Gui {
         for_install: bool,
         package: Package,
     ) -> Option<(bool, bool, Package)> {
-        match reqwest::get(&package.url).await {
-            Ok(response) => {
-                if response.status().is_client_error() {
-                    Some((false, for_install, package))
-                } else {
-                    Some((true, for_install, package))
+        let mut reachable = false;
+
+        // The package is available as long as any of its mirrors still has it.
+        for url in package.urls() {
+            match http::send(http::client().get(&url)).await {
+                Ok(response) => {
+                    mirrors::report(&url, !response.status().is_server_error());
+                    reachable = true;
+
+                    if !response.status().is_client_error() {
+                        return Some((true, for_install, package));
+                    }
                 }
+                Err(_) => mirrors::report(&url, false),
             }
-            Err(_) => {
-                CAN_CONNECT.store(false, Ordering::Relaxed);
-                None
-            }
+        }
+
+        if reachable {
+            Some((false, for_install, package))
+        } else {
+            CAN_CONNECT.store(false, Ordering::Relaxed);
+            None
         }
     }
 
@@ -98,6 +164,26 @@ impl Gui {
         package
     }
 
+    async fn commit_log(from: Package, to: Package) -> CommitLog {
+        let repository = get_setting().blender_repository.clone();
+        let commits = match repository {
+            Some(repository) => {
+                spawn_blocking(move || commits_between(&repository, &from.commit, &to.commit))
+                    .await
+                    .unwrap()
+            }
+            None => Err(String::from(
+                "set the location of a clone of Blender's repository in the settings first",
+            )),
+        };
+
+        CommitLog {
+            from: from.name,
+            to: to.name,
+            commits,
+        }
+    }
+
     async fn pass_string(string: String) -> String {
         string
     }
@@ -111,7 +197,7 @@ impl Gui {
             Lts,
         ),
     ) -> (
-        bool,
+        CheckResults,
         DailyLatest,
         ExperimentalLatest,
         PatchLatest,
@@ -124,7 +210,12 @@ impl Gui {
             Releases::check_updates(packages).await
         } else {
             (
-                false, packages.0, packages.1, packages.2, packages.3, packages.4,
+                CheckResults::new(),
+                packages.0,
+                packages.1,
+                packages.2,
+                packages.3,
+                packages.4,
             )
         }
     }
@@ -141,7 +232,7 @@ impl Gui {
         stable_archive: StableArchive,
         lts: Lts,
     ) -> (
-        bool,
+        CheckResults,
         DailyLatest,
         DailyArchive,
         ExperimentalLatest,
@@ -155,22 +246,30 @@ impl Gui {
         check_connection().await;
 
         if CAN_CONNECT.load(Ordering::Relaxed) {
-            let daily_latest = DailyLatest::check_updates(daily_latest).await.1;
-            let daily_archive = DailyArchive::check_updates(daily_archive).await.1;
-            let experimental_latest = ExperimentalLatest::check_updates(experimental_latest)
-                .await
-                .1;
-            let experimental_archive = ExperimentalArchive::check_updates(experimental_archive)
-                .await
-                .1;
-            let patch_latest = PatchLatest::check_updates(patch_latest).await.1;
-            let patch_archive = PatchArchive::check_updates(patch_archive).await.1;
-            let stable_latest = StableLatest::check_updates(stable_latest).await.1;
-            let stable_archive = StableArchive::check_updates(stable_archive).await.1;
-            let lts = Lts::check_updates(lts).await.1;
+            let mut results = CheckResults::new();
+            let (result, daily_latest) = DailyLatest::check_updates(daily_latest).await;
+            results.push((DailyLatest::NAME, result));
+            let (result, daily_archive) = DailyArchive::check_updates(daily_archive).await;
+            results.push((DailyArchive::NAME, result));
+            let (result, experimental_latest) =
+                ExperimentalLatest::check_updates(experimental_latest).await;
+            results.push((ExperimentalLatest::NAME, result));
+            let (result, experimental_archive) =
+                ExperimentalArchive::check_updates(experimental_archive).await;
+            results.push((ExperimentalArchive::NAME, result));
+            let (result, patch_latest) = PatchLatest::check_updates(patch_latest).await;
+            results.push((PatchLatest::NAME, result));
+            let (result, patch_archive) = PatchArchive::check_updates(patch_archive).await;
+            results.push((PatchArchive::NAME, result));
+            let (result, stable_latest) = StableLatest::check_updates(stable_latest).await;
+            results.push((StableLatest::NAME, result));
+            let (result, stable_archive) = StableArchive::check_updates(stable_archive).await;
+            results.push((StableArchive::NAME, result));
+            let (result, lts) = Lts::check_updates(lts).await;
+            results.push((Lts::NAME, result));
 
             (
-                true,
+                results,
                 daily_latest,
                 daily_archive,
                 experimental_latest,
@@ -183,7 +282,7 @@ impl Gui {
             )
         } else {
             (
-                false,
+                CheckResults::new(),
                 daily_latest,
                 daily_archive,
                 experimental_latest,
@@ -270,9 +369,35 @@ impl Application for Gui {
         }
 
         let mut state = GuiState::new();
+        state.profile_form.blender_configs = find_blender_configs()
+            .into_iter()
+            .map(|(version, path)| BlenderConfig { version, path })
+            .collect();
 
         let self_releases = flags.self_releases;
 
+        // Downloads that were active when the launcher closed are resumed along the queued ones,
+        // while those of packages that got installed or are no longer listed are forgotten.
+        let download_queue = get_setting()
+            .download_queue
+            .iter()
+            .filter_map(|download| {
+                let package = packages.iter().find(|package| {
+                    **package == download.package
+                        && !matches!(package.state, PackageState::Installed)
+                })?;
+
+                Some(QueuedDownload {
+                    package: package.clone(),
+                    source: download.source,
+                    state: match download.state {
+                        DownloadState::Paused => DownloadState::Paused,
+                        _ => DownloadState::Queued,
+                    },
+                })
+            })
+            .collect();
+
         if let Some(s_releases) = &self_releases {
             state.release_versions = s_releases
                 .iter()
@@ -280,17 +405,47 @@ impl Application for Gui {
                 .collect();
         }
 
+        let mut gui = Gui {
+            releases,
+            packages,
+            file_version: flags
+                .file_path
+                .as_ref()
+                .and_then(|file_path| saved_with(Path::new(file_path)).ok()),
+            file_pin: flags
+                .file_path
+                .as_ref()
+                .and_then(|file_path| VersionPin::find(Path::new(file_path))),
+            file_path: flags.file_path,
+            recent_files: get_setting().recent_files.to_vec(),
+            profiles: Profile::list(),
+            render_queue: get_setting().render_queue.clone(),
+            render: None,
+            render_queue_running: false,
+            compare_from: None,
+            commit_log: None,
+            fetch_errors: Vec::new(),
+            download_queue,
+            state,
+            controls: Controls::default(),
+            self_releases,
+        };
+
+        // Launching with a file pinned to a missing package is meant to offer installing it.
+        if let Some(pin) = &gui.file_pin {
+            if pin.installed(gui.releases.installed.iter()).is_none() {
+                set_setting().tab = Tab::RecentFiles;
+            }
+        }
+
+        if CAN_CONNECT.load(Ordering::Relaxed) {
+            gui.start_downloads();
+        } else {
+            gui.refresh_download_states();
+        }
+
         (
-            Gui {
-                releases,
-                packages,
-                file_path: flags.file_path,
-                recent_files: get_setting().recent_files.to_vec(),
-                installing: Vec::default(),
-                state,
-                controls: Controls::default(),
-                self_releases,
-            },
+            gui,
             Command::perform(Gui::calculate_disk_space(), GuiMessage::CalculateDiskSpace),
         )
     }
@@ -312,9 +467,15 @@ impl Application for Gui {
 
     fn subscription(&self) -> Subscription<GuiMessage> {
         Subscription::batch(
-            self.installing
+            self.download_queue
                 .iter()
-                .map(|package| Install::package(package.to_owned())),
+                .filter(|download| download.state == DownloadState::Active)
+                .map(|download| Install::package(download.package.clone(), download.source))
+                .chain(
+                    self.render
+                        .iter()
+                        .map(|render| Render::job(render.job.clone())),
+                ),
         )
     }
 
@@ -347,10 +508,19 @@ impl Application for Gui {
             }
         );
 
+        let downloads_tab_label = if self.download_queue.is_empty() {
+            String::from("Downloads")
+        } else {
+            format!("Downloads [{}]", self.download_queue.len())
+        };
+
         let tabs = Container::new(
             Row::new()
                 .push(tab_button("Recent files", Tab::RecentFiles))
                 .push(tab_button("Packages", Tab::Packages))
+                .push(tab_button(&downloads_tab_label, Tab::Downloads))
+                .push(tab_button("Profiles", Tab::Profiles))
+                .push(tab_button("Render queue", Tab::RenderQueue))
                 .push(tab_button("Settings", Tab::Settings))
                 .push(if get_setting().self_updater {
                     tab_button(&self_update_tab_label, Tab::SelfUpdater)
@@ -364,15 +534,63 @@ impl Application for Gui {
         .style(get_setting().theme.tab_container());
 
         let body = match current_tab {
-            Tab::RecentFiles => Tab::recent_files_body(self.file_path.clone(), &self.recent_files),
+            Tab::RecentFiles => Tab::recent_files_body(
+                self.file_path.clone(),
+                self.file_version.map(|version| {
+                    (
+                        version,
+                        closest_package(version, self.releases.installed.iter())
+                            .map(|package| package.name.clone()),
+                    )
+                }),
+                self.file_pin.as_ref().map(|pin| {
+                    (
+                        pin,
+                        pin.installed(self.releases.installed.iter()),
+                        pin.installable(self.packages.iter()),
+                    )
+                }),
+                &self.recent_files,
+                &self.profiles,
+            ),
             Tab::Packages => Tab::packages_body(
                 &self.packages,
                 self.file_path.clone(),
                 update_count,
                 file_exists,
                 &self.controls,
+                &self.profiles,
+                &self.compare_from,
+                &self.commit_log,
+                &self.fetch_errors,
+            ),
+            Tab::Downloads => Tab::downloads_body(&self.download_queue, &self.packages),
+            Tab::Profiles => Tab::profiles_body(&self.profiles, &self.state.profile_form),
+            Tab::RenderQueue => Tab::render_queue_body(
+                &self.render_queue,
+                &self.render,
+                self.render_queue_running,
+                &self.state.render_form,
+                self.recent_files
+                    .iter()
+                    .map(|recent_file| BlendFile(recent_file.path.clone()))
+                    .chain(self.state.render_form.file.clone())
+                    .unique()
+                    .collect(),
+                self.releases
+                    .installed
+                    .iter()
+                    .map(|package| package.name.clone())
+                    .collect(),
+            ),
+            Tab::Settings => Tab::settings_body(
+                &self.releases,
+                self.state.disk_space,
+                &self.state.custom_package,
+                &self.state.association_rule,
+                self.file_path.as_deref(),
+                &self.state.mirror_input,
             ),
-            Tab::Settings => Tab::settings_body(&self.releases, self.state.disk_space),
             Tab::SelfUpdater => Tab::self_updater_body(&self.state, &self.self_releases),
             Tab::About => Tab::about_body(),
         };
@@ -186,6 +186,13 @@ impl Controls {
                 GuiMessage::FilterLtsChanged,
                 true,
                 Some(GuiMessage::FetchLts),
+            ))
+            .push(filter_row(
+                get_setting().filters.custom,
+                String::from("Custom"),
+                GuiMessage::FilterCustomChanged,
+                false,
+                None,
             ));
 
         let sorting = Row::new()
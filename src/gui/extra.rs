@@ -1,7 +1,12 @@
-use crate::releases::Releases;
+use crate::{
+    profiles::Profile,
+    releases::Releases,
+    renderer::{RenderJob, RenderStatus},
+};
 use clap::crate_version;
 use iced::Executor;
 use self_update::update::Release;
+use std::path::PathBuf;
 
 #[derive(Debug)]
 pub struct GuiFlags {
@@ -17,6 +22,11 @@ pub struct GuiState {
     pub installing_release: bool,
     pub installed_release: bool,
     pub disk_space: Option<DiskSpace>,
+    pub custom_package: CustomPackageForm,
+    pub association_rule: AssociationRuleForm,
+    pub profile_form: ProfileForm,
+    pub render_form: RenderForm,
+    pub mirror_input: String,
 }
 
 impl GuiState {
@@ -74,6 +84,72 @@ pub enum Location {
     Cache,
 }
 
+/// What's been typed so far for registering a custom package.
+#[derive(Debug, Default)]
+pub struct CustomPackageForm {
+    pub name: String,
+    pub version: String,
+    pub label: String,
+    pub path: Option<PathBuf>,
+    pub error: Option<String>,
+}
+
+/// What's been typed so far for adding an association rule.
+#[derive(Debug, Default)]
+pub struct AssociationRuleForm {
+    pub pattern: String,
+    pub query: String,
+    pub profile: String,
+    pub error: Option<String>,
+}
+
+/// What's been chosen so far for creating a profile.
+#[derive(Debug, Default)]
+pub struct ProfileForm {
+    pub name: String,
+    pub blender_configs: Vec<BlenderConfig>,
+    pub clone_source: Option<BlenderConfig>,
+    pub error: Option<String>,
+}
+
+/// An existing configuration of Blender that a profile can be cloned from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BlenderConfig {
+    pub version: String,
+    pub path: PathBuf,
+}
+
+impl std::fmt::Display for BlenderConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Blender {}", self.version)
+    }
+}
+
+/// Profile picked for a package or recent file, where `None` means Blender's default one.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProfileChoice(pub Option<String>);
+
+impl ProfileChoice {
+    pub fn options(profiles: &[Profile]) -> Vec<ProfileChoice> {
+        std::iter::once(ProfileChoice(None))
+            .chain(
+                profiles
+                    .iter()
+                    .map(|profile| ProfileChoice(Some(profile.name.clone()))),
+            )
+            .collect()
+    }
+}
+
+impl std::fmt::Display for ProfileChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "Default"),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct DiskSpace {
     pub cache_size: f32,
@@ -81,3 +157,58 @@ pub struct DiskSpace {
     pub packages_size: f32,
     pub packages_available: f32,
 }
+
+/// What's been chosen so far for queueing a render.
+#[derive(Debug, Default)]
+pub struct RenderForm {
+    pub file: Option<BlendFile>,
+    pub package: Option<String>,
+    pub start: String,
+    pub end: String,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct BlendFile(pub PathBuf);
+
+impl std::fmt::Display for BlendFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.display())
+    }
+}
+
+/// The job currently being rendered and how far along it is.
+#[derive(Debug)]
+pub struct ActiveRender {
+    pub job: RenderJob,
+    pub status: Option<RenderStatus>,
+    pub frames_saved: u32,
+}
+
+impl ActiveRender {
+    pub fn new(job: RenderJob) -> Self {
+        Self {
+            job,
+            status: None,
+            frames_saved: 0,
+        }
+    }
+
+    /// Percentage of the whole job that's done, going by the samples of the current frame when
+    /// the amount of frames isn't known.
+    pub fn progress(&self) -> f32 {
+        let frame_progress = match &self.status {
+            Some(RenderStatus {
+                samples: Some((done, total)),
+                ..
+            }) if *total > 0 => *done as f32 / *total as f32,
+            _ => 0.0,
+        };
+
+        match self.job.frames.count() {
+            Some(count) => (self.frames_saved as f32 + frame_progress) / count as f32 * 100.0,
+            None => frame_progress * 100.0,
+        }
+        .min(100.0)
+    }
+}
@@ -5,6 +5,7 @@ use crate::{
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(default)]
 pub struct Filters {
     pub updates: bool,
     pub bookmarks: bool,
@@ -19,6 +20,7 @@ pub struct Filters {
     pub stable_latest: bool,
     pub stable_archive: bool,
     pub lts: bool,
+    pub custom: bool,
 }
 
 impl Filters {
@@ -46,6 +48,7 @@ impl Filters {
             } => {
                 self.stable_latest && *latest || self.stable_archive && *archive || self.lts && *lts
             }
+            BuildType::Custom => self.custom,
             BuildType::None => unreachable!("uninitialised build type"),
         };
 
@@ -86,6 +89,7 @@ impl Filters {
             && self.stable_latest
             && self.stable_archive
             && self.lts
+            && self.custom
     }
 }
 
@@ -105,6 +109,7 @@ impl Default for Filters {
             stable_latest: true,
             stable_archive: true,
             lts: true,
+            custom: true,
         }
     }
 }
@@ -1,5 +1,8 @@
 use super::{
-    extra::{BuildTypeSettings, Choice, DiskSpace, Location},
+    extra::{
+        ActiveRender, AssociationRuleForm, BlendFile, BlenderConfig, BuildTypeSettings, Choice,
+        CustomPackageForm, DiskSpace, Location, ProfileChoice,
+    },
     package::PackageMessage,
     sort_by::SortBy,
     style::Theme,
@@ -7,24 +10,34 @@ use super::{
     Gui, Tab,
 };
 use crate::{
-    helpers::open_blender,
-    package::{Build, Package},
+    association::AssociationRule,
+    blend_file::saved_with,
+    commit_log::CommitLog,
+    error::Result,
+    helpers::{confirm_opening, open_blender},
+    installer::{DownloadState, DownloadStatus, Progress, QueuedDownload, Source},
+    mirrors::Mirror,
+    package::{Build, Package, PackageState, Target},
+    profiles::{find_blender_configs, Profile},
     releases::{
         daily_archive::DailyArchive, daily_latest::DailyLatest,
         experimental_archive::ExperimentalArchive, experimental_latest::ExperimentalLatest,
         lts::Lts, patch_archive::PatchArchive, patch_latest::PatchLatest,
-        stable_archive::StableArchive, stable_latest::StableLatest, ReleaseType,
+        stable_archive::StableArchive, stable_latest::StableLatest, CheckResults, ReleaseType,
     },
+    renderer::{Frames, JobState, Progress as RenderProgress, RenderJob},
     settings::{
-        get_setting, save_settings, set_setting, ModifierKey, FETCHING, INSTALLING, PROJECT_DIRS,
+        get_setting, save_settings, set_setting, ModifierKey, CAN_CONNECT, FETCHING, INSTALLING,
+        PROJECT_DIRS,
     },
+    version_pin::VersionPin,
 };
 use iced::Command;
 use native_dialog::{FileDialog, MessageDialog, MessageType};
 use self_update::update::Release;
 use std::{
     fs::{create_dir_all, remove_dir_all},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::exit,
     sync::atomic::Ordering,
 };
@@ -36,6 +49,13 @@ pub enum GuiMessage {
     Bookmark(Package),
     CheckAvailability(Option<(bool, bool, Package)>),
     InstallPackage(Package),
+    InstallPackageFromCache(Package),
+    InstallationProgress((Package, Progress)),
+    PauseDownload(Package),
+    ResumeDownload(Package),
+    MoveDownloadUp(Package),
+    MoveDownloadDown(Package),
+    MaxConcurrentDownloadsChanged(usize),
     CancelInstall(Package),
     PackageInstalled(Package),
     PackageRemoved(Package),
@@ -46,7 +66,7 @@ pub enum GuiMessage {
     CheckForUpdates,
     UpdatesChecked(
         (
-            bool,
+            CheckResults,
             DailyLatest,
             ExperimentalLatest,
             PatchLatest,
@@ -57,7 +77,7 @@ pub enum GuiMessage {
     FetchAll,
     AllFetched(
         (
-            bool,
+            CheckResults,
             DailyLatest,
             DailyArchive,
             ExperimentalLatest,
@@ -71,23 +91,23 @@ pub enum GuiMessage {
     ),
     // TODO: Consider reducing all these message variations to one with an enum.
     FetchDailyLatest,
-    DailyLatestFetched((bool, DailyLatest)),
+    DailyLatestFetched((Result<bool>, DailyLatest)),
     FetchDailyArchive,
-    DailyArchiveFetched((bool, DailyArchive)),
+    DailyArchiveFetched((Result<bool>, DailyArchive)),
     FetchExperimentalLatest,
-    ExperimentalLatestFetched((bool, ExperimentalLatest)),
+    ExperimentalLatestFetched((Result<bool>, ExperimentalLatest)),
     FetchExperimentalArchive,
-    ExperimentalArchiveFetched((bool, ExperimentalArchive)),
+    ExperimentalArchiveFetched((Result<bool>, ExperimentalArchive)),
     FetchPatchLatest,
-    PatchLatestFetched((bool, PatchLatest)),
+    PatchLatestFetched((Result<bool>, PatchLatest)),
     FetchPatchArchive,
-    PatchArchiveFetched((bool, PatchArchive)),
+    PatchArchiveFetched((Result<bool>, PatchArchive)),
     FetchStableLatest,
-    StableLatestFetched((bool, StableLatest)),
+    StableLatestFetched((Result<bool>, StableLatest)),
     FetchStableArchive,
-    StableArchiveFetched((bool, StableArchive)),
+    StableArchiveFetched((Result<bool>, StableArchive)),
     FetchLts,
-    LtsFetched((bool, Lts)),
+    LtsFetched((Result<bool>, Lts)),
     FilterUpdatesChanged(bool),
     FilterBookmarksChanged(bool),
     FilterInstalledChanged(bool),
@@ -101,12 +121,15 @@ pub enum GuiMessage {
     FilterStableLatestChanged(bool),
     FilterStableArchiveChanged(bool),
     FilterLtsChanged(bool),
+    FilterCustomChanged(bool),
     SortingChanged(SortBy),
     TabChanged(Tab),
     CalculateDiskSpace(DiskSpace),
     BypassLauncher(Choice),
     ModifierKey(ModifierKey),
     UseLatestAsDefault(Choice),
+    OpenWithClosestPackage(Choice),
+    BackUpRiskyFiles(Choice),
     CheckUpdatesAtLaunch(Choice),
     MinutesBetweenUpdatesChanged(i64),
     UpdateDailyLatest(Choice),
@@ -120,6 +143,46 @@ pub enum GuiMessage {
     RemoveDatabases(BuildTypeSettings),
     RemovePackages(BuildTypeSettings),
     RemoveCache,
+    CustomPackageNameChanged(String),
+    CustomPackageVersionChanged(String),
+    CustomPackageLabelChanged(String),
+    PickCustomPackageDirectory,
+    PickCustomPackageExecutable,
+    RegisterCustomPackage,
+    AssociationPatternChanged(String),
+    AssociationQueryChanged(String),
+    AssociationProfileChanged(String),
+    AddAssociationRule,
+    RemoveAssociationRule(usize),
+    MoveAssociationRuleUp(usize),
+    MoveAssociationRuleDown(usize),
+    ProfileNameChanged(String),
+    CreateProfile,
+    BlenderConfigSelected(BlenderConfig),
+    CloneProfile,
+    CloneProfileFromDirectory,
+    DeleteProfile(Profile),
+    RenderFileSelected(BlendFile),
+    SelectRenderFile,
+    RenderPackageSelected(String),
+    RenderStartChanged(String),
+    RenderEndChanged(String),
+    QueueRender,
+    StartRenderQueue,
+    StopRenderQueue,
+    RenderProgress((u64, RenderProgress)),
+    RequeueRenderJob(u64),
+    RemoveRenderJob(u64),
+    Compare(Package),
+    CommitLogLoaded(CommitLog),
+    CloseCommitLog,
+    SelectBlenderRepository,
+    UnsetBlenderRepository,
+    MirrorInputChanged(String),
+    AddMirror,
+    RemoveMirror(String),
+    ResetMirrors,
+    ToggleTarget(Target),
     SelfUpdater(Choice),
     CheckSelfUpdatesAtLaunch(Choice),
     FetchSelfReleases,
@@ -132,6 +195,209 @@ pub enum GuiMessage {
 }
 
 impl Gui {
+    fn profile_created(&mut self, result: Result<Profile, String>) {
+        match result {
+            Ok(profile) => {
+                println!("Created profile: {}", profile.name);
+                self.state.profile_form.name.clear();
+                self.state.profile_form.error = None;
+                self.profiles = Profile::list();
+            }
+            Err(message) => self.state.profile_form.error = Some(message),
+        }
+    }
+
+    /// Set the file to open, along with the version of Blender it was saved with and the package
+    /// its project is pinned to.
+    fn select_file(&mut self, file: String) {
+        self.file_version = saved_with(Path::new(&file)).ok();
+        self.file_pin = VersionPin::find(Path::new(&file));
+        self.file_path = Some(file);
+    }
+
+    /// The package to open the selected file with, which is the one its project is pinned to
+    /// unless the user would rather keep the chosen one when asked. Nothing is opened while the
+    /// pinned package isn't installed, which is told and leaves the file selected in the recent
+    /// files tab, where installing it is offered.
+    fn pinned_package(&self, chosen: String) -> Option<String> {
+        match &self.file_pin {
+            Some(pin) => match pin.installed(self.releases.installed.iter()) {
+                Some(package) if package.name == chosen => Some(chosen),
+                Some(package) => {
+                    let message = format!(
+                        "'{}' pins '{}', which is '{}' instead of the chosen '{}'.\n\n\
+                        Open the file with the pinned package?",
+                        pin.file.display(),
+                        pin.query,
+                        package.name,
+                        chosen
+                    );
+                    match MessageDialog::new()
+                        .set_type(MessageType::Info)
+                        .set_title("Ablavema")
+                        .set_text(&message)
+                        .show_confirm()
+                    {
+                        Ok(true) => Some(package.name.clone()),
+                        Ok(false) => Some(chosen),
+                        Err(_) => {
+                            // Without a dialog there's nobody to ask, so the pin is followed.
+                            println!(
+                                "Opening with '{}', which '{}' pins, instead of '{}'.",
+                                package.name,
+                                pin.file.display(),
+                                chosen
+                            );
+                            Some(package.name.clone())
+                        }
+                    }
+                }
+                None => {
+                    let message = format!(
+                        "'{}' pins '{}', which isn't installed.",
+                        pin.file.display(),
+                        pin.query
+                    );
+                    set_setting().tab = Tab::RecentFiles;
+                    if MessageDialog::new()
+                        .set_type(MessageType::Info)
+                        .set_title("Ablavema")
+                        .set_text(&message)
+                        .show_alert()
+                        .is_err()
+                    {
+                        #[cfg(target_os = "linux")]
+                        println!(
+                            "Error: {}\nProbably need to install 'zenity' or 'kdialog' for a graphical dialog.",
+                            &message
+                        );
+                    }
+                    None
+                }
+            },
+            None => Some(chosen),
+        }
+    }
+
+    fn set_render_job_state(&mut self, id: u64, state: JobState) {
+        if let Some(job) = set_setting()
+            .render_queue
+            .iter_mut()
+            .find(|job| job.id == id)
+        {
+            job.state = state;
+        }
+        save_settings();
+        self.render_queue = get_setting().render_queue.clone();
+    }
+
+    fn save_download_queue(&mut self) {
+        set_setting().download_queue = self.download_queue.clone();
+        save_settings();
+        INSTALLING.store(
+            self.download_queue
+                .iter()
+                .any(|download| download.state == DownloadState::Active),
+            Ordering::Relaxed,
+        );
+    }
+
+    fn queue_download(&mut self, package: Package, source: Source) {
+        if !self
+            .download_queue
+            .iter()
+            .any(|download| download.package == package)
+        {
+            self.download_queue.push(QueuedDownload {
+                package,
+                source,
+                state: DownloadState::Queued,
+            });
+        }
+        self.start_downloads();
+    }
+
+    fn set_download_state(&mut self, package: &Package, state: DownloadState) {
+        if let Some(download) = self
+            .download_queue
+            .iter_mut()
+            .find(|download| download.package == *package)
+        {
+            download.state = state;
+        }
+        self.start_downloads();
+    }
+
+    /// Start the queued downloads in order until `max_concurrent_downloads` of them are active.
+    /// Only the active ones get a subscription, so pausing a download drops it and leaves its
+    /// partial file behind to be resumed later.
+    pub(super) fn start_downloads(&mut self) {
+        let active = self
+            .download_queue
+            .iter()
+            .filter(|download| download.state == DownloadState::Active)
+            .count();
+        let mut free = get_setting()
+            .max_concurrent_downloads
+            .max(1)
+            .saturating_sub(active);
+
+        for download in self.download_queue.iter_mut() {
+            if free == 0 {
+                break;
+            }
+            if download.state == DownloadState::Queued {
+                download.state = DownloadState::Active;
+                free -= 1;
+            }
+        }
+
+        self.save_download_queue();
+        self.refresh_download_states();
+    }
+
+    /// Show the packages in the download queue as such, since their state is lost whenever the
+    /// packages get rebuilt.
+    pub(super) fn refresh_download_states(&mut self) {
+        for download in &self.download_queue {
+            if let Some(package) = self
+                .packages
+                .iter_mut()
+                .find(|package| **package == download.package)
+            {
+                match download.state {
+                    DownloadState::Queued => package.state = PackageState::Queued,
+                    DownloadState::Paused => package.state = PackageState::Paused,
+                    DownloadState::Active => {
+                        if !matches!(
+                            package.state,
+                            PackageState::Downloading { .. } | PackageState::Extracting { .. }
+                        ) {
+                            package.state = PackageState::Downloading {
+                                status: DownloadStatus::default(),
+                            };
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Render the next queued job if the queue is running and nothing is being rendered, or stop
+    /// the queue if there's nothing left in it.
+    fn start_next_render(&mut self) {
+        if self.render_queue_running && self.render.is_none() {
+            match self
+                .render_queue
+                .iter()
+                .find(|job| job.state == JobState::Queued)
+            {
+                Some(job) => self.render = Some(ActiveRender::new(job.clone())),
+                None => self.render_queue_running = false,
+            }
+        }
+    }
+
     pub fn update_message(&mut self, message: GuiMessage) -> Command<GuiMessage> {
         match message {
             GuiMessage::PackageMessage((index, package_message)) => {
@@ -143,18 +409,30 @@ impl Gui {
             GuiMessage::RecentFileMessage((file, recent_file_message)) => match recent_file_message
             {
                 RecentFileMessage::OpenWithLastBlender(blender) => {
-                    self.file_path = Some(file);
+                    self.select_file(file);
                     Command::perform(Gui::pass_string(blender), GuiMessage::OpenBlenderWithFile)
                 }
                 RecentFileMessage::OpenWithDefaultBlender => {
-                    self.file_path = Some(file);
+                    self.select_file(file);
                     Command::perform(
                         Gui::pass_string(get_setting().default_package.clone().unwrap().name),
                         GuiMessage::OpenBlenderWithFile,
                     )
                 }
                 RecentFileMessage::Select => {
-                    self.file_path = Some(file);
+                    self.select_file(file);
+                    Command::none()
+                }
+                RecentFileMessage::Render => {
+                    let path = PathBuf::from(file);
+                    self.state.render_form.package = get_setting()
+                        .recent_files
+                        .get(&path)
+                        .map(|recent_file| recent_file.last_opened_with.clone());
+                    self.state.render_form.file = Some(BlendFile(path));
+                    self.state.render_form.error = None;
+                    set_setting().tab = Tab::RenderQueue;
+                    save_settings();
                     Command::none()
                 }
                 RecentFileMessage::Remove => {
@@ -163,6 +441,16 @@ impl Gui {
                     self.recent_files = get_setting().recent_files.to_vec();
                     Command::none()
                 }
+                RecentFileMessage::ProfileSelected(ProfileChoice(profile)) => {
+                    if let Some(recent_file) =
+                        set_setting().recent_files.get_mut(&PathBuf::from(file))
+                    {
+                        recent_file.profile = profile;
+                    }
+                    save_settings();
+                    self.recent_files = get_setting().recent_files.to_vec();
+                    Command::none()
+                }
             },
             GuiMessage::Bookmark(package) => {
                 set_setting().bookmarks.update(package.name);
@@ -177,6 +465,17 @@ impl Gui {
                     } else if !for_install {
                         self.sync();
                         Command::none()
+                    } else if package.cached {
+                        // The archive outlived the package online, so there's no need to
+                        // forget about it while it can still be installed from the cache.
+                        println!(
+                            "Package '{}' is no longer available, installing it from the cache.",
+                            package.name
+                        );
+                        Command::perform(
+                            Gui::pass_package(package),
+                            GuiMessage::InstallPackageFromCache,
+                        )
                     } else {
                         match package.build {
                             Build::DailyLatest(_) => {
@@ -187,7 +486,9 @@ impl Gui {
                                     .position(|a_package| *a_package == package)
                                     .unwrap();
                                 self.releases.daily_latest.remove(index);
-                                self.releases.daily_latest.save();
+                                if let Err(e) = self.releases.daily_latest.save() {
+                                    eprintln!("Couldn't save the {}", e);
+                                }
                             }
                             Build::DailyArchive(_) => {
                                 let index = self
@@ -197,7 +498,9 @@ impl Gui {
                                     .position(|a_package| *a_package == package)
                                     .unwrap();
                                 self.releases.daily_archive.remove(index);
-                                self.releases.daily_archive.save();
+                                if let Err(e) = self.releases.daily_archive.save() {
+                                    eprintln!("Couldn't save the {}", e);
+                                }
                             }
                             Build::ExperimentalLatest(_) => {
                                 let index = self
@@ -207,7 +510,9 @@ impl Gui {
                                     .position(|a_package| *a_package == package)
                                     .unwrap();
                                 self.releases.experimental_latest.remove(index);
-                                self.releases.experimental_latest.save();
+                                if let Err(e) = self.releases.experimental_latest.save() {
+                                    eprintln!("Couldn't save the {}", e);
+                                }
                             }
                             Build::ExperimentalArchive(_) => {
                                 let index = self
@@ -217,7 +522,9 @@ impl Gui {
                                     .position(|a_package| *a_package == package)
                                     .unwrap();
                                 self.releases.experimental_archive.remove(index);
-                                self.releases.experimental_archive.save();
+                                if let Err(e) = self.releases.experimental_archive.save() {
+                                    eprintln!("Couldn't save the {}", e);
+                                }
                             }
                             Build::PatchLatest(_) => {
                                 let index = self
@@ -227,7 +534,9 @@ impl Gui {
                                     .position(|a_package| *a_package == package)
                                     .unwrap();
                                 self.releases.patch_latest.remove(index);
-                                self.releases.patch_latest.save();
+                                if let Err(e) = self.releases.patch_latest.save() {
+                                    eprintln!("Couldn't save the {}", e);
+                                }
                             }
                             Build::PatchArchive(_) => {
                                 let index = self
@@ -237,7 +546,9 @@ impl Gui {
                                     .position(|a_package| *a_package == package)
                                     .unwrap();
                                 self.releases.patch_archive.remove(index);
-                                self.releases.patch_archive.save();
+                                if let Err(e) = self.releases.patch_archive.save() {
+                                    eprintln!("Couldn't save the {}", e);
+                                }
                             }
                             Build::StableLatest => {
                                 let index = self
@@ -247,7 +558,9 @@ impl Gui {
                                     .position(|a_package| *a_package == package)
                                     .unwrap();
                                 self.releases.stable_latest.remove(index);
-                                self.releases.stable_latest.save();
+                                if let Err(e) = self.releases.stable_latest.save() {
+                                    eprintln!("Couldn't save the {}", e);
+                                }
                             }
                             Build::StableArchive => {
                                 let index = self
@@ -257,7 +570,9 @@ impl Gui {
                                     .position(|a_package| *a_package == package)
                                     .unwrap();
                                 self.releases.stable_archive.remove(index);
-                                self.releases.stable_archive.save();
+                                if let Err(e) = self.releases.stable_archive.save() {
+                                    eprintln!("Couldn't save the {}", e);
+                                }
                             }
                             Build::Lts => {
                                 let index = self
@@ -267,8 +582,11 @@ impl Gui {
                                     .position(|a_package| *a_package == package)
                                     .unwrap();
                                 self.releases.lts.remove(index);
-                                self.releases.lts.save();
+                                if let Err(e) = self.releases.lts.save() {
+                                    eprintln!("Couldn't save the {}", e);
+                                }
                             }
+                            Build::Custom => unreachable!("custom packages aren't downloaded"),
                         }
                         if for_install {
                             let message =
@@ -299,41 +617,98 @@ impl Gui {
                 }
             },
             GuiMessage::InstallPackage(package) => {
-                if self.installing.is_empty() {
-                    INSTALLING.store(true, Ordering::Relaxed);
+                self.queue_download(package, Source::Download);
+                Command::none()
+            }
+            GuiMessage::InstallPackageFromCache(package) => {
+                self.queue_download(package, Source::Cache);
+                Command::none()
+            }
+            GuiMessage::InstallationProgress((package, progress)) => {
+                match self
+                    .packages
+                    .iter_mut()
+                    .find(|a_package| **a_package == package)
+                {
+                    Some(a_package) => {
+                        a_package.update(PackageMessage::InstallationProgress(progress))
+                    }
+                    // The package may no longer be listed, but the queue still has to move on.
+                    None => match progress {
+                        Progress::FinishedInstalling => Command::perform(
+                            Gui::pass_package(package),
+                            GuiMessage::PackageInstalled,
+                        ),
+                        Progress::Errored(_) => {
+                            Command::perform(Gui::pass_package(package), GuiMessage::CancelInstall)
+                        }
+                        _ => Command::none(),
+                    },
                 }
-                self.installing.push(package);
+            }
+            GuiMessage::PauseDownload(package) => {
+                self.set_download_state(&package, DownloadState::Paused);
                 Command::none()
             }
-            GuiMessage::CancelInstall(package) => {
-                let index = self
-                    .installing
+            GuiMessage::ResumeDownload(package) => {
+                self.set_download_state(&package, DownloadState::Queued);
+                Command::none()
+            }
+            GuiMessage::MoveDownloadUp(package) => {
+                if let Some(index) = self
+                    .download_queue
                     .iter()
-                    .enumerate()
-                    .find(|(_, a_package)| *a_package == &package)
-                    .unwrap()
-                    .0;
-                self.installing.remove(index);
-                if self.installing.is_empty() {
-                    INSTALLING.store(false, Ordering::Relaxed);
+                    .position(|download| download.package == package)
+                {
+                    if index > 0 {
+                        self.download_queue.swap(index, index - 1);
+                        self.save_download_queue();
+                    }
                 }
                 Command::none()
             }
-            GuiMessage::PackageInstalled(package) => {
-                let index = self
-                    .installing
+            GuiMessage::MoveDownloadDown(package) => {
+                if let Some(index) = self
+                    .download_queue
                     .iter()
-                    .enumerate()
-                    .find(|(_, a_package)| *a_package == &package)
-                    .unwrap()
-                    .0;
-                self.installing.remove(index);
+                    .position(|download| download.package == package)
+                {
+                    if index + 1 < self.download_queue.len() {
+                        self.download_queue.swap(index, index + 1);
+                        self.save_download_queue();
+                    }
+                }
+                Command::none()
+            }
+            GuiMessage::MaxConcurrentDownloadsChanged(amount) => {
+                // Lowering the limit lets the active downloads finish instead of pausing them.
+                set_setting().max_concurrent_downloads = amount.clamp(1, 10);
+                save_settings();
+                self.start_downloads();
+                Command::none()
+            }
+            GuiMessage::CancelInstall(package) => {
+                self.download_queue
+                    .retain(|download| download.package != package);
+                if let Some(a_package) = self
+                    .packages
+                    .iter_mut()
+                    .find(|a_package| **a_package == package)
+                {
+                    if !matches!(a_package.state, PackageState::Errored { .. }) {
+                        a_package.state = PackageState::default();
+                    }
+                }
+                self.start_downloads();
+                Command::none()
+            }
+            GuiMessage::PackageInstalled(package) => {
+                self.download_queue
+                    .retain(|download| download.package != package);
                 self.releases.installed.fetch();
                 self.releases.installed.update_default();
                 self.sync();
-                if self.installing.is_empty() {
-                    INSTALLING.store(false, Ordering::Relaxed);
-                }
+                self.start_downloads();
                 Command::none()
             }
             GuiMessage::PackageRemoved(package) => {
@@ -344,6 +719,10 @@ impl Gui {
                         save_settings();
                     }
                 }
+                if package.build == Build::Custom {
+                    self.sync();
+                    return Command::none();
+                }
                 Command::perform(
                     Gui::check_availability(false, package),
                     GuiMessage::CheckAvailability,
@@ -354,9 +733,26 @@ impl Gui {
                 exit(0);
             }
             GuiMessage::OpenBlenderWithFile(package) => {
+                let package = match self.pinned_package(package) {
+                    Some(package) => package,
+                    None => return Command::none(),
+                };
                 let file_path = self.file_path.clone().unwrap();
                 let path = PathBuf::from(&file_path);
-                let recent_file = RecentFile::new(path.clone(), package.clone());
+                if let Some(installed) = self
+                    .releases
+                    .installed
+                    .iter()
+                    .find(|installed| installed.name == package)
+                {
+                    if !confirm_opening(&path, installed) {
+                        return Command::none();
+                    }
+                }
+                let mut recent_file = RecentFile::new(path.clone(), package.clone());
+                if let Some(old_recent_file) = get_setting().recent_files.get(&path) {
+                    recent_file.profile = old_recent_file.profile.clone();
+                }
                 set_setting().recent_files.insert(path, recent_file);
                 save_settings();
                 open_blender(package, Some(file_path));
@@ -369,7 +765,7 @@ impl Gui {
                     .show_open_single_file()
                     .unwrap()
                 {
-                    self.file_path = Some(new_file_path.to_str().unwrap().to_string());
+                    self.select_file(new_file_path.to_str().unwrap().to_string());
                 }
                 Command::none()
             }
@@ -384,7 +780,8 @@ impl Gui {
                     GuiMessage::UpdatesChecked,
                 )
             }
-            GuiMessage::UpdatesChecked(tuple) => {
+            GuiMessage::UpdatesChecked(mut tuple) => {
+                self.record_checks(std::mem::take(&mut tuple.0));
                 self.releases.add_new_packages(tuple);
                 self.sync();
                 FETCHING.store(false, Ordering::Relaxed);
@@ -408,7 +805,7 @@ impl Gui {
                 )
             }
             GuiMessage::AllFetched((
-                _,
+                results,
                 daily_latest,
                 daily_archive,
                 experimental_latest,
@@ -428,6 +825,7 @@ impl Gui {
                 self.releases.stable_latest = stable_latest;
                 self.releases.stable_archive = stable_archive;
                 self.releases.lts = lts;
+                self.record_checks(results);
                 self.sync();
                 FETCHING.store(false, Ordering::Relaxed);
                 Command::none()
@@ -439,7 +837,8 @@ impl Gui {
                     GuiMessage::DailyLatestFetched,
                 )
             }
-            GuiMessage::DailyLatestFetched((_, daily_latest)) => {
+            GuiMessage::DailyLatestFetched((result, daily_latest)) => {
+                self.record_checks(vec![(DailyLatest::NAME, result)]);
                 self.releases.daily_latest = daily_latest;
                 self.sync();
                 FETCHING.store(false, Ordering::Relaxed);
@@ -452,7 +851,8 @@ impl Gui {
                     GuiMessage::DailyArchiveFetched,
                 )
             }
-            GuiMessage::DailyArchiveFetched((_, daily_archive)) => {
+            GuiMessage::DailyArchiveFetched((result, daily_archive)) => {
+                self.record_checks(vec![(DailyArchive::NAME, result)]);
                 self.releases.daily_archive = daily_archive;
                 self.sync();
                 FETCHING.store(false, Ordering::Relaxed);
@@ -465,7 +865,8 @@ impl Gui {
                     GuiMessage::ExperimentalLatestFetched,
                 )
             }
-            GuiMessage::ExperimentalLatestFetched((_, experimental_latest)) => {
+            GuiMessage::ExperimentalLatestFetched((result, experimental_latest)) => {
+                self.record_checks(vec![(ExperimentalLatest::NAME, result)]);
                 self.releases.experimental_latest = experimental_latest;
                 self.sync();
                 FETCHING.store(false, Ordering::Relaxed);
@@ -478,7 +879,8 @@ impl Gui {
                     GuiMessage::ExperimentalArchiveFetched,
                 )
             }
-            GuiMessage::ExperimentalArchiveFetched((_, experimental_archive)) => {
+            GuiMessage::ExperimentalArchiveFetched((result, experimental_archive)) => {
+                self.record_checks(vec![(ExperimentalArchive::NAME, result)]);
                 self.releases.experimental_archive = experimental_archive;
                 self.sync();
                 FETCHING.store(false, Ordering::Relaxed);
@@ -491,7 +893,8 @@ impl Gui {
                     GuiMessage::PatchLatestFetched,
                 )
             }
-            GuiMessage::PatchLatestFetched((_, patch_latest)) => {
+            GuiMessage::PatchLatestFetched((result, patch_latest)) => {
+                self.record_checks(vec![(PatchLatest::NAME, result)]);
                 self.releases.patch_latest = patch_latest;
                 self.sync();
                 FETCHING.store(false, Ordering::Relaxed);
@@ -504,7 +907,8 @@ impl Gui {
                     GuiMessage::PatchArchiveFetched,
                 )
             }
-            GuiMessage::PatchArchiveFetched((_, patch_archive)) => {
+            GuiMessage::PatchArchiveFetched((result, patch_archive)) => {
+                self.record_checks(vec![(PatchArchive::NAME, result)]);
                 self.releases.patch_archive = patch_archive;
                 self.sync();
                 FETCHING.store(false, Ordering::Relaxed);
@@ -517,7 +921,8 @@ impl Gui {
                     GuiMessage::StableLatestFetched,
                 )
             }
-            GuiMessage::StableLatestFetched((_, stable_latest)) => {
+            GuiMessage::StableLatestFetched((result, stable_latest)) => {
+                self.record_checks(vec![(StableLatest::NAME, result)]);
                 self.releases.stable_latest = stable_latest;
                 self.sync();
                 FETCHING.store(false, Ordering::Relaxed);
@@ -530,7 +935,8 @@ impl Gui {
                     GuiMessage::StableArchiveFetched,
                 )
             }
-            GuiMessage::StableArchiveFetched((_, stable_archive)) => {
+            GuiMessage::StableArchiveFetched((result, stable_archive)) => {
+                self.record_checks(vec![(StableArchive::NAME, result)]);
                 self.releases.stable_archive = stable_archive;
                 self.sync();
                 FETCHING.store(false, Ordering::Relaxed);
@@ -543,7 +949,8 @@ impl Gui {
                     GuiMessage::LtsFetched,
                 )
             }
-            GuiMessage::LtsFetched((_, lts)) => {
+            GuiMessage::LtsFetched((result, lts)) => {
+                self.record_checks(vec![(Lts::NAME, result)]);
                 self.releases.lts = lts;
                 self.sync();
                 FETCHING.store(false, Ordering::Relaxed);
@@ -576,6 +983,7 @@ impl Gui {
                 set_setting().filters.stable_latest = change;
                 set_setting().filters.stable_archive = change;
                 set_setting().filters.lts = change;
+                set_setting().filters.custom = change;
                 save_settings();
                 Command::none()
             }
@@ -633,6 +1041,12 @@ impl Gui {
                 save_settings();
                 Command::none()
             }
+            GuiMessage::FilterCustomChanged(change) => {
+                set_setting().filters.custom = change;
+                set_setting().filters.refresh_all();
+                save_settings();
+                Command::none()
+            }
             GuiMessage::SortingChanged(sort_by) => {
                 set_setting().sort_by = sort_by;
                 save_settings();
@@ -641,6 +1055,12 @@ impl Gui {
             GuiMessage::TabChanged(tab) => {
                 set_setting().tab = tab;
                 save_settings();
+                if tab == Tab::Profiles {
+                    self.state.profile_form.blender_configs = find_blender_configs()
+                        .into_iter()
+                        .map(|(version, path)| BlenderConfig { version, path })
+                        .collect();
+                }
                 if tab == Tab::Settings {
                     Command::perform(Gui::calculate_disk_space(), GuiMessage::CalculateDiskSpace)
                 } else {
@@ -672,6 +1092,22 @@ impl Gui {
                 save_settings();
                 Command::none()
             }
+            GuiMessage::OpenWithClosestPackage(choice) => {
+                match choice {
+                    Choice::Enable => set_setting().open_with_closest_package = true,
+                    Choice::Disable => set_setting().open_with_closest_package = false,
+                }
+                save_settings();
+                Command::none()
+            }
+            GuiMessage::BackUpRiskyFiles(choice) => {
+                match choice {
+                    Choice::Enable => set_setting().back_up_risky_files = true,
+                    Choice::Disable => set_setting().back_up_risky_files = false,
+                }
+                save_settings();
+                Command::none()
+            }
             GuiMessage::CheckUpdatesAtLaunch(choice) => {
                 match choice {
                     Choice::Enable => set_setting().check_updates_at_launch = true,
@@ -867,6 +1303,333 @@ impl Gui {
                 create_dir_all(&get_setting().cache_dir).unwrap();
                 Command::perform(Gui::calculate_disk_space(), GuiMessage::CalculateDiskSpace)
             }
+            GuiMessage::ProfileNameChanged(name) => {
+                self.state.profile_form.name = name;
+                Command::none()
+            }
+            GuiMessage::CreateProfile => {
+                let result = Profile::create(&self.state.profile_form.name);
+                self.profile_created(result);
+                Command::none()
+            }
+            GuiMessage::BlenderConfigSelected(blender_config) => {
+                self.state.profile_form.clone_source = Some(blender_config);
+                Command::none()
+            }
+            GuiMessage::CloneProfile => {
+                let form = &self.state.profile_form;
+                let result =
+                    Profile::clone_from(&form.name, &form.clone_source.clone().unwrap().path);
+                self.profile_created(result);
+                Command::none()
+            }
+            GuiMessage::CloneProfileFromDirectory => {
+                if let Some(directory) = FileDialog::new().show_open_single_dir().unwrap() {
+                    let result = Profile::clone_from(&self.state.profile_form.name, &directory);
+                    self.profile_created(result);
+                }
+                Command::none()
+            }
+            GuiMessage::DeleteProfile(profile) => {
+                profile.delete();
+                self.profiles = Profile::list();
+                self.recent_files = get_setting().recent_files.to_vec();
+                Command::none()
+            }
+            GuiMessage::RenderFileSelected(file) => {
+                self.state.render_form.file = Some(file);
+                Command::none()
+            }
+            GuiMessage::SelectRenderFile => {
+                if let Some(file) = FileDialog::new()
+                    .add_filter("BLEND archive", &["blend"])
+                    .add_filter("All files", &["*"])
+                    .show_open_single_file()
+                    .unwrap()
+                {
+                    self.state.render_form.file = Some(BlendFile(file));
+                }
+                Command::none()
+            }
+            GuiMessage::RenderPackageSelected(package) => {
+                self.state.render_form.package = Some(package);
+                Command::none()
+            }
+            GuiMessage::RenderStartChanged(start) => {
+                self.state.render_form.start = start;
+                Command::none()
+            }
+            GuiMessage::RenderEndChanged(end) => {
+                self.state.render_form.end = end;
+                Command::none()
+            }
+            GuiMessage::QueueRender => {
+                let form = &mut self.state.render_form;
+                let job = match (&form.file, &form.package) {
+                    (None, _) => Err(String::from("pick a file to render")),
+                    (_, None) => Err(String::from("pick a package to render with")),
+                    (Some(BlendFile(file)), Some(package)) => {
+                        Frames::from_input(&form.start, &form.end).map(|frames| RenderJob {
+                            id: get_setting()
+                                .render_queue
+                                .iter()
+                                .map(|job| job.id + 1)
+                                .max()
+                                .unwrap_or_default(),
+                            file: file.clone(),
+                            package: package.clone(),
+                            frames,
+                            state: JobState::Queued,
+                        })
+                    }
+                };
+
+                match job {
+                    Ok(job) => {
+                        form.error = None;
+                        set_setting().render_queue.push(job);
+                        save_settings();
+                        self.render_queue = get_setting().render_queue.clone();
+                        self.start_next_render();
+                    }
+                    Err(message) => form.error = Some(message),
+                }
+                Command::none()
+            }
+            GuiMessage::StartRenderQueue => {
+                self.render_queue_running = true;
+                self.start_next_render();
+                Command::none()
+            }
+            GuiMessage::StopRenderQueue => {
+                // Dropping the render's subscription kills Blender, leaving the job queued.
+                self.render_queue_running = false;
+                self.render = None;
+                Command::none()
+            }
+            GuiMessage::RenderProgress((id, progress)) => {
+                let render = match &mut self.render {
+                    Some(render) if render.job.id == id => render,
+                    _ => return Command::none(),
+                };
+
+                match progress {
+                    RenderProgress::Started => (),
+                    RenderProgress::Status(status) => render.status = Some(status),
+                    RenderProgress::FrameSaved => render.frames_saved += 1,
+                    RenderProgress::Finished => {
+                        println!("Rendered: {}", render.job.file.display());
+                        self.render = None;
+                        self.set_render_job_state(id, JobState::Finished);
+                        self.start_next_render();
+                    }
+                    RenderProgress::Errored(message) => {
+                        eprintln!(
+                            "Render of '{}' failed: {}",
+                            render.job.file.display(),
+                            message
+                        );
+                        self.render = None;
+                        self.set_render_job_state(id, JobState::Failed(message));
+                        self.start_next_render();
+                    }
+                }
+                Command::none()
+            }
+            GuiMessage::RequeueRenderJob(id) => {
+                self.set_render_job_state(id, JobState::Queued);
+                self.start_next_render();
+                Command::none()
+            }
+            GuiMessage::RemoveRenderJob(id) => {
+                set_setting().render_queue.retain(|job| job.id != id);
+                save_settings();
+                self.render_queue = get_setting().render_queue.clone();
+                if matches!(&self.render, Some(render) if render.job.id == id) {
+                    self.render = None;
+                    self.start_next_render();
+                }
+                Command::none()
+            }
+            GuiMessage::Compare(package) => match self.compare_from.take() {
+                Some(from) if from != package => {
+                    Command::perform(Gui::commit_log(from, package), GuiMessage::CommitLogLoaded)
+                }
+                Some(_) => Command::none(),
+                None => {
+                    self.compare_from = Some(package);
+                    Command::none()
+                }
+            },
+            GuiMessage::CommitLogLoaded(commit_log) => {
+                self.commit_log = Some(commit_log);
+                Command::none()
+            }
+            GuiMessage::CloseCommitLog => {
+                self.commit_log = None;
+                Command::none()
+            }
+            GuiMessage::SelectBlenderRepository => {
+                if let Some(directory) = FileDialog::new().show_open_single_dir().unwrap() {
+                    set_setting().blender_repository = Some(directory);
+                    save_settings();
+                }
+                Command::none()
+            }
+            GuiMessage::UnsetBlenderRepository => {
+                set_setting().blender_repository = None;
+                save_settings();
+                Command::none()
+            }
+            GuiMessage::CustomPackageNameChanged(name) => {
+                self.state.custom_package.name = name;
+                Command::none()
+            }
+            GuiMessage::CustomPackageVersionChanged(version) => {
+                self.state.custom_package.version = version;
+                Command::none()
+            }
+            GuiMessage::CustomPackageLabelChanged(label) => {
+                self.state.custom_package.label = label;
+                Command::none()
+            }
+            GuiMessage::PickCustomPackageDirectory => {
+                if let Some(directory) = FileDialog::new().show_open_single_dir().unwrap() {
+                    self.state.custom_package.path = Some(directory);
+                }
+                Command::none()
+            }
+            GuiMessage::PickCustomPackageExecutable => {
+                if let Some(file) = FileDialog::new().show_open_single_file().unwrap() {
+                    self.state.custom_package.path = Some(file);
+                }
+                Command::none()
+            }
+            GuiMessage::RegisterCustomPackage => {
+                let form = &self.state.custom_package;
+                let result = Package::new_custom(
+                    &form.name,
+                    &form.version,
+                    &form.label,
+                    form.path.clone().unwrap_or_default(),
+                )
+                .and_then(|package| self.releases.register_custom_package(package));
+
+                match result {
+                    Ok(()) => {
+                        self.state.custom_package = CustomPackageForm::default();
+                        self.packages = self.releases.build_vec();
+                    }
+                    Err(message) => self.state.custom_package.error = Some(message),
+                }
+                Command::none()
+            }
+            GuiMessage::AssociationPatternChanged(pattern) => {
+                self.state.association_rule.pattern = pattern;
+                Command::none()
+            }
+            GuiMessage::AssociationQueryChanged(query) => {
+                self.state.association_rule.query = query;
+                Command::none()
+            }
+            GuiMessage::AssociationProfileChanged(profile) => {
+                self.state.association_rule.profile = profile;
+                Command::none()
+            }
+            GuiMessage::AddAssociationRule => {
+                let form = &mut self.state.association_rule;
+                let pattern = form.pattern.trim();
+                let profile = Some(form.profile.trim())
+                    .filter(|profile| !profile.is_empty())
+                    .map(str::to_string);
+
+                let result = if pattern.is_empty() {
+                    Err(String::from("the pattern can't be empty"))
+                } else if profile
+                    .as_ref()
+                    .is_some_and(|profile| Profile::get(profile).is_none())
+                {
+                    Err(format!("profile '{}' not found", profile.unwrap()))
+                } else {
+                    form.query.parse().map(|query| AssociationRule {
+                        pattern: pattern.to_string(),
+                        query,
+                        profile,
+                    })
+                };
+
+                match result {
+                    Ok(rule) => {
+                        set_setting().association_rules.push(rule);
+                        save_settings();
+                        self.state.association_rule = AssociationRuleForm::default();
+                    }
+                    Err(message) => form.error = Some(message),
+                }
+                Command::none()
+            }
+            GuiMessage::RemoveAssociationRule(index) => {
+                let mut settings = set_setting();
+                if index < settings.association_rules.len() {
+                    settings.association_rules.remove(index);
+                }
+                drop(settings);
+                save_settings();
+                Command::none()
+            }
+            GuiMessage::MoveAssociationRuleUp(index) => {
+                if index > 0 && index < get_setting().association_rules.len() {
+                    set_setting().association_rules.swap(index, index - 1);
+                    save_settings();
+                }
+                Command::none()
+            }
+            GuiMessage::MoveAssociationRuleDown(index) => {
+                if index + 1 < get_setting().association_rules.len() {
+                    set_setting().association_rules.swap(index, index + 1);
+                    save_settings();
+                }
+                Command::none()
+            }
+            GuiMessage::MirrorInputChanged(url) => {
+                self.state.mirror_input = url;
+                Command::none()
+            }
+            GuiMessage::AddMirror => {
+                let mirror = Mirror::new(&self.state.mirror_input);
+                if mirror.url.starts_with("http")
+                    && !get_setting()
+                        .mirrors
+                        .iter()
+                        .any(|a_mirror| a_mirror.url == mirror.url)
+                {
+                    set_setting().mirrors.push(mirror);
+                    save_settings();
+                    self.state.mirror_input.clear();
+                }
+                Command::none()
+            }
+            GuiMessage::RemoveMirror(url) => {
+                set_setting().mirrors.retain(|mirror| mirror.url != url);
+                save_settings();
+                Command::none()
+            }
+            GuiMessage::ResetMirrors => {
+                set_setting().mirrors = Mirror::defaults();
+                save_settings();
+                Command::none()
+            }
+            GuiMessage::ToggleTarget(target) => {
+                let mut settings = set_setting();
+                if !settings.targets.contains(&target) {
+                    settings.targets.push(target);
+                } else if settings.targets.len() > 1 {
+                    settings.targets.retain(|a_target| *a_target != target);
+                }
+                drop(settings);
+                save_settings();
+                Command::none()
+            }
             GuiMessage::SelfUpdater(choice) => {
                 match choice {
                     Choice::Enable => set_setting().self_updater = true,
@@ -923,6 +1686,9 @@ impl Gui {
             }
             GuiMessage::ConnectionChecked(()) => {
                 self.controls.checking_connection = false;
+                if CAN_CONNECT.load(Ordering::Relaxed) {
+                    self.start_downloads();
+                }
                 Command::none()
             }
         }
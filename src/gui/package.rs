@@ -1,22 +1,29 @@
-use super::{install::Progress, Gui, GuiMessage};
+use super::{extra::ProfileChoice, Gui, GuiMessage};
 use crate::{
-    package::{Package, PackageState, PackageStatus},
+    helpers::format_bytes,
+    installer::{DownloadStatus, Progress},
+    package::{Build, Change, ChangelogView, Package, PackageState, PackageStatus},
+    profiles::Profile,
     settings::{get_setting, save_settings, set_setting, CAN_CONNECT, FETCHING, TEXT_SIZE},
 };
 use iced::{
     alignment::Horizontal,
     pure::{
-        widget::{Button, Column, Container, Row, Text},
+        widget::{Button, Column, Container, PickList, Row, Text},
         Element,
     },
     Alignment, Command, Length, ProgressBar,
 };
+use itertools::Itertools;
 use std::sync::atomic::Ordering;
 
 #[derive(Clone, Debug)]
 pub enum PackageMessage {
     Install,
+    InstallFromCache,
     InstallationProgress(Progress),
+    Pause,
+    Resume,
     Cancel,
     Remove,
     OpenBlender,
@@ -24,6 +31,10 @@ pub enum PackageMessage {
     SetDefault,
     UnsetDefault,
     Bookmark,
+    Compare,
+    ToggleChangelog(ChangelogView),
+    OpenChange(String),
+    ProfileSelected(ProfileChoice),
 }
 
 impl Package {
@@ -33,24 +44,33 @@ impl Package {
                 Gui::check_availability(true, self.clone()),
                 GuiMessage::CheckAvailability,
             ),
+            PackageMessage::InstallFromCache => Command::perform(
+                Gui::pass_package(self.clone()),
+                GuiMessage::InstallPackageFromCache,
+            ),
             PackageMessage::InstallationProgress(progress) => match progress {
                 Progress::Started => {
-                    self.state = PackageState::Downloading { progress: 0.0 };
+                    self.state = PackageState::Downloading {
+                        status: DownloadStatus::default(),
+                    };
                     Command::none()
                 }
-                Progress::DownloadProgress(progress) => {
+                Progress::DownloadProgress(status) => {
                     if let PackageState::Downloading { .. } = self.state {
-                        self.state = PackageState::Downloading { progress };
+                        self.state = PackageState::Downloading { status };
                     }
                     Command::none()
                 }
                 Progress::FinishedDownloading => {
-                    self.state = PackageState::Extracting { progress: 0.0 };
+                    self.state = PackageState::Extracting {
+                        extracted: 0,
+                        total: 0,
+                    };
                     Command::none()
                 }
-                Progress::ExtractionProgress(progress) => {
+                Progress::ExtractionProgress { extracted, total } => {
                     if let PackageState::Extracting { .. } = self.state {
-                        self.state = PackageState::Extracting { progress };
+                        self.state = PackageState::Extracting { extracted, total };
                     }
                     Command::none()
                 }
@@ -67,6 +87,12 @@ impl Package {
                     Command::perform(Gui::pass_package(self.clone()), GuiMessage::CancelInstall)
                 }
             },
+            PackageMessage::Pause => {
+                Command::perform(Gui::pass_package(self.clone()), GuiMessage::PauseDownload)
+            }
+            PackageMessage::Resume => {
+                Command::perform(Gui::pass_package(self.clone()), GuiMessage::ResumeDownload)
+            }
             PackageMessage::Cancel => {
                 self.state = PackageState::default();
                 Command::perform(Gui::pass_package(self.clone()), GuiMessage::CancelInstall)
@@ -96,10 +122,43 @@ impl Package {
             PackageMessage::Bookmark => {
                 Command::perform(Gui::pass_package(self.clone()), GuiMessage::Bookmark)
             }
+            PackageMessage::Compare => {
+                Command::perform(Gui::pass_package(self.clone()), GuiMessage::Compare)
+            }
+            PackageMessage::ToggleChangelog(view) => {
+                self.changelog_view = if self.changelog_view == Some(view) {
+                    None
+                } else {
+                    Some(view)
+                };
+                Command::none()
+            }
+            PackageMessage::OpenChange(url) => {
+                Command::perform(Gui::pass_string(url), GuiMessage::OpenBrowser)
+            }
+            PackageMessage::ProfileSelected(ProfileChoice(profile)) => {
+                match profile {
+                    Some(profile) => {
+                        set_setting()
+                            .package_profiles
+                            .insert(self.name.clone(), profile);
+                    }
+                    None => {
+                        set_setting().package_profiles.remove(&self.name);
+                    }
+                }
+                save_settings();
+                Command::none()
+            }
         }
     }
 
-    pub fn view(&self, file_exists: bool, is_odd: bool) -> Element<'_, PackageMessage> {
+    pub fn view(
+        &self,
+        file_exists: bool,
+        is_odd: bool,
+        profiles: &[Profile],
+    ) -> Element<'_, PackageMessage> {
         let is_default_package = get_setting().default_package.is_some()
             && get_setting().default_package.clone().unwrap() == *self;
 
@@ -113,6 +172,15 @@ impl Package {
                     .size(TEXT_SIZE + 10)
                     .width(Length::Fill),
             )
+            .push({
+                let button = Button::new(Text::new("[C]")).style(get_setting().theme);
+
+                if self.commit.is_empty() {
+                    button
+                } else {
+                    button.on_press(PackageMessage::Compare)
+                }
+            })
             .push(
                 Button::new(Text::new(if get_setting().bookmarks.contains(&self.name) {
                     "[B]"
@@ -166,6 +234,159 @@ impl Package {
                     ),
             );
 
+        let details = if self.label.is_empty() {
+            details
+        } else {
+            details.push(
+                Row::new()
+                    .align_items(Alignment::End)
+                    .push(Text::new("Label: ").size(TEXT_SIZE - 4))
+                    .push(Text::new(&self.label).color(get_setting().theme.highlight_text())),
+            )
+        };
+
+        let runs_here = self.runs_here();
+        let details = if runs_here {
+            details
+        } else {
+            details.push(
+                Row::new()
+                    .align_items(Alignment::End)
+                    .push(Text::new("Target: ").size(TEXT_SIZE - 4))
+                    .push(
+                        Text::new(self.target().to_string())
+                            .color(get_setting().theme.highlight_text()),
+                    ),
+            )
+        };
+
+        let changelog_button = |label, view| {
+            Button::new(Text::new(label))
+                .on_press(PackageMessage::ToggleChangelog(view))
+                .style(get_setting().theme)
+        };
+
+        let show_newer_changes =
+            matches!(self.state, PackageState::Installed) && !self.newer_changes.is_empty();
+
+        let details = if self.changelog.is_empty() && !show_newer_changes {
+            details
+        } else {
+            let buttons = Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(Text::new("Changelog: ").size(TEXT_SIZE - 4));
+
+            let buttons = if self.changelog.is_empty() {
+                buttons
+            } else {
+                buttons.push(changelog_button(
+                    format!(
+                        "[L] {} {} change{}",
+                        if self.changelog_view == Some(ChangelogView::Own) {
+                            "Hide"
+                        } else {
+                            "Show"
+                        },
+                        self.changelog.len(),
+                        if self.changelog.len() == 1 { "" } else { "s" }
+                    ),
+                    ChangelogView::Own,
+                ))
+            };
+
+            let buttons = if show_newer_changes {
+                buttons.push(changelog_button(
+                    format!(
+                        "[N] {} {} change{} since this version",
+                        if self.changelog_view == Some(ChangelogView::NewerReleases) {
+                            "Hide"
+                        } else {
+                            "Show"
+                        },
+                        self.newer_changes.len(),
+                        if self.newer_changes.len() == 1 {
+                            ""
+                        } else {
+                            "s"
+                        }
+                    ),
+                    ChangelogView::NewerReleases,
+                ))
+            } else {
+                buttons
+            };
+
+            details.push(buttons)
+        };
+
+        let change_row = |change: &Change| {
+            let row = Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(Text::new(format!("- {}", change.text)).width(Length::Fill));
+
+            if change.url.is_empty() {
+                row
+            } else {
+                row.push(
+                    Button::new(Text::new("[>]"))
+                        .on_press(PackageMessage::OpenChange(change.url.clone()))
+                        .style(get_setting().theme),
+                )
+            }
+        };
+
+        let details = match self.changelog_view {
+            Some(ChangelogView::Own) => details.push(
+                self.changelog
+                    .iter()
+                    .fold(Column::new().spacing(5).padding(10), |column, change| {
+                        column.push(change_row(change))
+                    }),
+            ),
+            Some(ChangelogView::NewerReleases) if show_newer_changes => details.push(
+                self.newer_changes
+                    .iter()
+                    .group_by(|(version, _)| version)
+                    .into_iter()
+                    .fold(
+                        Column::new().spacing(5).padding(10),
+                        |column, (version, changes)| {
+                            changes.fold(
+                                column.push(
+                                    Text::new(format!("Blender {}", version))
+                                        .color(get_setting().theme.highlight_text()),
+                                ),
+                                |column, (_, change)| column.push(change_row(change)),
+                            )
+                        },
+                    ),
+            ),
+            _ => details,
+        };
+
+        let details = if matches!(self.state, PackageState::Installed) && !profiles.is_empty() {
+            details.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(Text::new("Profile: ").size(TEXT_SIZE - 4))
+                    .push(
+                        PickList::new(
+                            ProfileChoice::options(profiles),
+                            Some(ProfileChoice(
+                                get_setting().package_profiles.get(&self.name).cloned(),
+                            )),
+                            PackageMessage::ProfileSelected,
+                        )
+                        .style(get_setting().theme),
+                    ),
+            )
+        } else {
+            details
+        };
+
         let button = |label, package_message: Option<PackageMessage>| {
             let button = Button::new(Text::new(label).horizontal_alignment(Horizontal::Center))
                 .width(Length::Fill)
@@ -178,78 +399,119 @@ impl Package {
         };
 
         let controls: Element<'_, PackageMessage> = match &self.state {
-            PackageState::Fetched => Row::new()
-                .push(button(
+            PackageState::Fetched => {
+                let install = Row::new().spacing(10).push(button(
                     "[#] Install",
                     if CAN_CONNECT.load(Ordering::Relaxed) && !FETCHING.load(Ordering::Relaxed) {
                         Some(PackageMessage::Install)
                     } else {
                         None
                     },
-                ))
-                .into(),
-            PackageState::Downloading { progress } => Row::new()
+                ));
+
+                if self.cached {
+                    install
+                        .push(button(
+                            "[#] Install from cache",
+                            Some(PackageMessage::InstallFromCache),
+                        ))
+                        .into()
+                } else {
+                    install.into()
+                }
+            }
+            PackageState::Queued | PackageState::Paused => {
+                let paused = matches!(self.state, PackageState::Paused);
+
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(
+                        Text::new(if paused {
+                            "Paused, waiting to be resumed."
+                        } else {
+                            "Queued, waiting for a download slot."
+                        })
+                        .width(Length::Fill),
+                    )
+                    .push(
+                        Button::new(Text::new(if paused { "Resume" } else { "Pause" }))
+                            .on_press(if paused {
+                                PackageMessage::Resume
+                            } else {
+                                PackageMessage::Pause
+                            })
+                            .style(get_setting().theme),
+                    )
+                    .push(
+                        Button::new(Text::new("Cancel"))
+                            .on_press(PackageMessage::Cancel)
+                            .style(get_setting().theme),
+                    )
+                    .into()
+            }
+            PackageState::Downloading { status } => Row::new()
                 .spacing(10)
                 .align_items(Alignment::Center)
-                .push(Text::new(format!("Downloading... {:.2}%", progress)))
+                .push(Text::new(format!(
+                    "Downloading... {:.2}% ({})",
+                    status.percentage(),
+                    status
+                )))
                 .push(
-                    ProgressBar::new(0.0..=100.0, *progress)
+                    ProgressBar::new(0.0..=100.0, status.percentage())
                         .width(Length::Fill)
                         .style(get_setting().theme),
                 )
+                .push(
+                    Button::new(Text::new("Pause"))
+                        .on_press(PackageMessage::Pause)
+                        .style(get_setting().theme),
+                )
                 .push(
                     Button::new(Text::new("Cancel"))
                         .on_press(PackageMessage::Cancel)
                         .style(get_setting().theme),
                 )
                 .into(),
-            PackageState::Extracting { progress } => {
-                // TODO: Figure out why cancelling doesn't work for extraction.
-                // It does visually get cancelled, but the extraction keeps going in the
-                // background, ultimately getting installed. But since the package was supposedly
-                // removed from the installation process, the program crashes at the end when it
-                // tries that, since it's no longer there. The same behaviour happens on Windows,
-                // where the extraction works differently. I thought maybe the download kept going
-                // as well, but no, that stops as intended when cancelled.
-                if cfg!(target_os = "linux") {
-                    Row::new()
-                        .align_items(Alignment::Center)
-                        .push(
-                            Text::new("Extracting...")
-                                .width(Length::Fill)
-                                .horizontal_alignment(Horizontal::Center),
-                        )
-                        /* .push(
-                            Button::new(cancel_button, Text::new("Cancel"))
-                                .on_press(PackageMessage::Cancel)
-                                .style(theme),
-                        ) */
-                        .into()
+            PackageState::Extracting { extracted, total } => {
+                let progress = if *total == 0 {
+                    0.0
                 } else {
-                    Row::new()
-                        .spacing(10)
-                        .align_items(Alignment::Center)
-                        .push(Text::new(format!("Extracting... {:.2}%", progress)))
-                        .push(
-                            ProgressBar::new(0.0..=100.0, *progress)
-                                .width(Length::Fill)
-                                .style(get_setting().theme),
-                        )
-                        /* .push(
-                            Button::new(cancel_button, Text::new("Cancel"))
-                                .on_press(PackageMessage::Cancel)
-                                .style(theme),
-                        ) */
-                        .into()
-                }
+                    *extracted as f32 / *total as f32 * 100.0
+                };
+
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(Text::new(format!(
+                        "Extracting... {:.2}% ({} of {})",
+                        progress,
+                        format_bytes(*extracted),
+                        format_bytes(*total)
+                    )))
+                    .push(
+                        ProgressBar::new(0.0..=100.0, progress)
+                            .width(Length::Fill)
+                            .style(get_setting().theme),
+                    )
+                    .push(
+                        Button::new(Text::new("Cancel"))
+                            .on_press(PackageMessage::Cancel)
+                            .style(get_setting().theme),
+                    )
+                    .into()
             }
             PackageState::Installed => {
-                let button1 =
-                    Row::new().push(button("[=] Open", Some(PackageMessage::OpenBlender)));
+                // Packages built for other machines are only kept around, not launched.
+                let button1 = Row::new().push(button(
+                    "[=] Open",
+                    runs_here.then_some(PackageMessage::OpenBlender),
+                ));
 
                 let button2 = button1.push(button(
                     "[+] Open file",
-                    if file_exists {
+                    if file_exists && runs_here {
                         Some(PackageMessage::OpenBlenderWithFile)
                     } else {
                         None
@@ -265,13 +527,20 @@ impl Package {
                     if is_default_package {
                         Some(PackageMessage::UnsetDefault)
                     } else {
-                        Some(PackageMessage::SetDefault)
+                        runs_here.then_some(PackageMessage::SetDefault)
                     },
                 ));
 
                 button3
                     .spacing(10)
-                    .push(button("[X] Uninstall", Some(PackageMessage::Remove)))
+                    .push(button(
+                        if self.build == Build::Custom {
+                            "[X] Unregister"
+                        } else {
+                            "[X] Uninstall"
+                        },
+                        Some(PackageMessage::Remove),
+                    ))
                     .into()
             }
             PackageState::Errored {
@@ -0,0 +1,45 @@
+use super::GuiMessage;
+use crate::renderer::{self, Progress, RenderJob};
+use iced_futures::{
+    futures::stream::{unfold, BoxStream},
+    subscription,
+};
+use std::hash::{Hash, Hasher};
+use tokio::sync::mpsc::unbounded_channel;
+
+pub struct Render(RenderJob);
+
+impl Render {
+    pub fn job(job: RenderJob) -> iced::Subscription<GuiMessage> {
+        iced::Subscription::from_recipe(Render(job)).map(GuiMessage::RenderProgress)
+    }
+}
+
+impl<H, I> subscription::Recipe<H, I> for Render
+where
+    H: Hasher,
+{
+    type Output = (u64, Progress);
+
+    fn hash(&self, state: &mut H) {
+        std::any::TypeId::of::<Self>().hash(state);
+        self.0.id.hash(state);
+    }
+
+    fn stream(self: Box<Self>, _input: BoxStream<'static, I>) -> BoxStream<'static, Self::Output> {
+        let id = self.0.id;
+        let (sender, receiver) = unbounded_channel();
+        tokio::spawn(renderer::render(self.0, sender));
+
+        Box::pin(unfold(receiver, move |mut receiver| async move {
+            match receiver.recv().await {
+                Some(progress) => Some(((id, progress), receiver)),
+                None => {
+                    let _: () = iced::futures::future::pending().await;
+
+                    None
+                }
+            }
+        }))
+    }
+}
@@ -1,5 +1,5 @@
 use iced::{
-    pure::widget::{button, checkbox, container, pick_list, progress_bar, radio, rule},
+    pure::widget::{button, checkbox, container, pick_list, progress_bar, radio, rule, text_input},
     Color,
 };
 use serde::{Deserialize, Serialize};
@@ -173,6 +173,15 @@ impl From<Theme> for Box<dyn pick_list::StyleSheet> {
     }
 }
 
+impl From<Theme> for Box<dyn text_input::StyleSheet> {
+    fn from(theme: Theme) -> Self {
+        match theme {
+            Theme::Light => light::TextInput.into(),
+            Theme::Dark => dark::TextInput.into(),
+        }
+    }
+}
+
 impl From<Theme> for Box<dyn rule::StyleSheet> {
     fn from(theme: Theme) -> Self {
         match theme {
@@ -184,7 +193,9 @@ impl From<Theme> for Box<dyn rule::StyleSheet> {
 
 mod light {
     use iced::{
-        pure::widget::{button, checkbox, container, pick_list, progress_bar, radio, rule},
+        pure::widget::{
+            button, checkbox, container, pick_list, progress_bar, radio, rule, text_input,
+        },
         Color, Vector,
     };
 
@@ -427,6 +438,45 @@ mod light {
         }
     }
 
+    pub struct TextInput;
+    impl text_input::StyleSheet for TextInput {
+        fn active(&self) -> text_input::Style {
+            text_input::Style {
+                background: PICK_LIST_BACKGROUND.into(),
+                border_radius: 5.0,
+                border_width: 0.0,
+                border_color: Color::TRANSPARENT,
+            }
+        }
+
+        fn focused(&self) -> text_input::Style {
+            text_input::Style {
+                border_width: 1.0,
+                border_color: ACTIVE,
+                ..self.active()
+            }
+        }
+
+        fn hovered(&self) -> text_input::Style {
+            text_input::Style {
+                background: HOVERED.into(),
+                ..self.active()
+            }
+        }
+
+        fn placeholder_color(&self) -> Color {
+            Color { a: 0.5, ..TEXT }
+        }
+
+        fn value_color(&self) -> Color {
+            ACTIVE_TEXT
+        }
+
+        fn selection_color(&self) -> Color {
+            ACTIVE
+        }
+    }
+
     pub struct Rule;
     impl rule::StyleSheet for Rule {
         fn style(&self) -> rule::Style {
@@ -442,7 +492,9 @@ mod light {
 
 mod dark {
     use iced::{
-        pure::widget::{button, checkbox, container, pick_list, progress_bar, radio, rule},
+        pure::widget::{
+            button, checkbox, container, pick_list, progress_bar, radio, rule, text_input,
+        },
         Color, Vector,
     };
 
@@ -685,6 +737,45 @@ mod dark {
         }
     }
 
+    pub struct TextInput;
+    impl text_input::StyleSheet for TextInput {
+        fn active(&self) -> text_input::Style {
+            text_input::Style {
+                background: PICK_LIST_BACKGROUND.into(),
+                border_radius: 5.0,
+                border_width: 0.0,
+                border_color: Color::TRANSPARENT,
+            }
+        }
+
+        fn focused(&self) -> text_input::Style {
+            text_input::Style {
+                border_width: 1.0,
+                border_color: ACTIVE,
+                ..self.active()
+            }
+        }
+
+        fn hovered(&self) -> text_input::Style {
+            text_input::Style {
+                background: HOVERED.into(),
+                ..self.active()
+            }
+        }
+
+        fn placeholder_color(&self) -> Color {
+            Color { a: 0.5, ..TEXT }
+        }
+
+        fn value_color(&self) -> Color {
+            ACTIVE_TEXT
+        }
+
+        fn selection_color(&self) -> Color {
+            ACTIVE
+        }
+    }
+
     pub struct Rule;
     impl rule::StyleSheet for Rule {
         fn style(&self) -> rule::Style {
@@ -1,7 +1,10 @@
 use serde::{Deserialize, Serialize};
 pub mod about;
+pub mod downloads;
 pub mod packages;
+pub mod profiles;
 pub mod recent_files;
+pub mod render_queue;
 pub mod self_updater;
 pub mod settings;
 
@@ -9,6 +12,9 @@ pub mod settings;
 pub enum Tab {
     RecentFiles,
     Packages,
+    Downloads,
+    Profiles,
+    RenderQueue,
     Settings,
     SelfUpdater,
     About,
@@ -0,0 +1,226 @@
+use super::Tab;
+use crate::{
+    gui::message::GuiMessage,
+    helpers::format_bytes,
+    installer::{DownloadState, QueuedDownload, Source},
+    package::{Package, PackageState},
+    settings::{get_setting, TEXT_SIZE},
+};
+use iced::{
+    alignment::Horizontal,
+    pure::{
+        widget::{Button, Column, Container, Row, Scrollable, Text},
+        Element,
+    },
+    Alignment, Length, ProgressBar, Space,
+};
+
+impl<'a> Tab {
+    pub fn downloads_body(
+        download_queue: &'a [QueuedDownload],
+        packages: &'a [Package],
+    ) -> Element<'a, GuiMessage> {
+        let button = |label, message: Option<GuiMessage>| {
+            let button = Button::new(Text::new(label).horizontal_alignment(Horizontal::Center))
+                .style(get_setting().theme);
+
+            match message {
+                Some(message) => button.on_press(message),
+                None => button,
+            }
+        };
+
+        let max_concurrent_downloads = get_setting().max_concurrent_downloads;
+
+        let info: Element<'_, GuiMessage> = Container::new(
+            Row::new()
+                .padding(10)
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(
+                    Text::new(
+                        "Packages are installed in the order they're queued in. Lowering the \
+                        limit lets the active downloads finish.",
+                    )
+                    .width(Length::Fill),
+                )
+                .push(Text::new("Concurrent downloads:"))
+                .push(button(
+                    "-",
+                    (max_concurrent_downloads > 1).then(|| {
+                        GuiMessage::MaxConcurrentDownloadsChanged(max_concurrent_downloads - 1)
+                    }),
+                ))
+                .push(
+                    Text::new(max_concurrent_downloads.to_string())
+                        .color(get_setting().theme.highlight_text()),
+                )
+                .push(button(
+                    "+",
+                    (max_concurrent_downloads < 10).then(|| {
+                        GuiMessage::MaxConcurrentDownloadsChanged(max_concurrent_downloads + 1)
+                    }),
+                )),
+        )
+        .width(Length::Fill)
+        .style(get_setting().theme.info_container())
+        .into();
+
+        let download_queue_view: Element<'_, GuiMessage> = if download_queue.is_empty() {
+            Container::new(Text::new("No downloads queued").size(TEXT_SIZE * 2))
+                .height(Length::Fill)
+                .width(Length::Fill)
+                .center_x()
+                .center_y()
+                .style(get_setting().theme)
+                .into()
+        } else {
+            let mut download_count: u16 = 0;
+            let list = download_queue
+                .iter()
+                .enumerate()
+                .fold(Column::new(), |column, (index, download)| {
+                    download_count += 1;
+                    let package = &download.package;
+                    let state = packages
+                        .iter()
+                        .find(|a_package| *a_package == package)
+                        .map(|a_package| &a_package.state);
+
+                    let detail = |label, value: String| {
+                        Row::new()
+                            .align_items(Alignment::End)
+                            .push(Text::new(label).size(TEXT_SIZE - 4))
+                            .push(Text::new(value).color(get_setting().theme.highlight_text()))
+                    };
+
+                    let progress = |label: String, progress: f32| {
+                        Row::new()
+                            .spacing(10)
+                            .align_items(Alignment::Center)
+                            .push(Text::new(label))
+                            .push(
+                                ProgressBar::new(0.0..=100.0, progress)
+                                    .width(Length::Fill)
+                                    .style(get_setting().theme),
+                            )
+                    };
+
+                    // Pausing an extraction would download the archive again once resumed, since
+                    // it's already complete.
+                    let extracting = matches!(state, Some(PackageState::Extracting { .. }));
+
+                    let state: Element<'_, GuiMessage> = match (download.state, state) {
+                        (DownloadState::Active, Some(PackageState::Downloading { status })) => {
+                            progress(
+                                format!("Downloading... {:.2}% ({})", status.percentage(), status),
+                                status.percentage(),
+                            )
+                            .into()
+                        }
+                        (
+                            DownloadState::Active,
+                            Some(PackageState::Extracting { extracted, total }),
+                        ) => {
+                            let p = if *total == 0 {
+                                0.0
+                            } else {
+                                *extracted as f32 / *total as f32 * 100.0
+                            };
+
+                            progress(
+                                format!(
+                                    "Extracting... {:.2}% ({} of {})",
+                                    p,
+                                    format_bytes(*extracted),
+                                    format_bytes(*total)
+                                ),
+                                p,
+                            )
+                            .into()
+                        }
+                        (DownloadState::Active, _) => {
+                            detail("State: ", String::from("Starting...")).into()
+                        }
+                        (DownloadState::Queued, _) => {
+                            detail("State: ", String::from("Queued")).into()
+                        }
+                        (DownloadState::Paused, _) => {
+                            detail("State: ", String::from("Paused")).into()
+                        }
+                    };
+
+                    let controls = Row::new()
+                        .spacing(10)
+                        .push(Space::with_width(Length::Fill))
+                        .push(button(
+                            "[^] Up",
+                            (index > 0).then(|| GuiMessage::MoveDownloadUp(package.clone())),
+                        ))
+                        .push(button(
+                            "[v] Down",
+                            (index + 1 < download_queue.len())
+                                .then(|| GuiMessage::MoveDownloadDown(package.clone())),
+                        ))
+                        .push(match download.state {
+                            DownloadState::Paused => button(
+                                "[>] Resume",
+                                Some(GuiMessage::ResumeDownload(package.clone())),
+                            ),
+                            _ => button(
+                                "[||] Pause",
+                                (!extracting).then(|| GuiMessage::PauseDownload(package.clone())),
+                            ),
+                        })
+                        .push(button(
+                            "[X] Cancel",
+                            Some(GuiMessage::CancelInstall(package.clone())),
+                        ));
+
+                    column.push(
+                        Container::new(
+                            Column::new()
+                                .spacing(10)
+                                .push(
+                                    Text::new(&package.name)
+                                        .color(get_setting().theme.highlight_text())
+                                        .size(TEXT_SIZE + 10),
+                                )
+                                .push(detail("Version: ", package.version.to_string()))
+                                .push(detail("Build: ", package.build_type.to_string()))
+                                .push(detail(
+                                    "Source: ",
+                                    String::from(match download.source {
+                                        Source::Download => "Download",
+                                        Source::Cache => "Cache",
+                                    }),
+                                ))
+                                .push(state)
+                                .push(controls),
+                        )
+                        .width(Length::Fill)
+                        .padding(10)
+                        .style(if download_count & 1 != 0 {
+                            get_setting().theme.odd_container()
+                        } else {
+                            get_setting().theme.even_container()
+                        }),
+                    )
+                })
+                .width(Length::Fill);
+
+            Container::new(Scrollable::new(list))
+                .height(Length::Fill)
+                .width(Length::Fill)
+                .style(get_setting().theme.normal_container())
+                .into()
+        };
+
+        Container::new(Column::new().push(info).push(download_queue_view))
+            .height(Length::Fill)
+            .width(Length::Fill)
+            .center_x()
+            .style(get_setting().theme.normal_container())
+            .into()
+    }
+}
@@ -1,7 +1,10 @@
 use super::Tab;
 use crate::{
+    commit_log::CommitLog,
+    error::AblavemaError,
     gui::{controls::Controls, message::GuiMessage},
     package::Package,
+    profiles::Profile,
     releases::UpdateCount,
     settings::{get_setting, FETCHING, TEXT_SIZE},
 };
@@ -16,12 +19,17 @@ use itertools::Itertools;
 use std::sync::atomic::Ordering;
 
 impl<'a> Tab {
+    #[allow(clippy::too_many_arguments)]
     pub fn packages_body(
         packages: &'a [Package],
         file_path: Option<String>,
         update_count: UpdateCount,
         file_exists: bool,
         controls: &'a Controls,
+        profiles: &'a [Profile],
+        compare_from: &'a Option<Package>,
+        commit_log: &'a Option<CommitLog>,
+        fetch_errors: &'a [(&'static str, AblavemaError)],
     ) -> Element<'a, GuiMessage> {
         // TODO: Use real icons for the buttons.
         // TODO: Add tooltips.
@@ -89,13 +97,39 @@ impl<'a> Tab {
                                 .on_press(GuiMessage::SelectFile)
                                 .style(get_setting().theme),
                         ),
-                ),
+                )
+                .push(match compare_from {
+                    Some(package) => Row::new()
+                        .spacing(10)
+                        .align_items(Alignment::Center)
+                        .push(button("[C]", Some(GuiMessage::Compare(package.to_owned()))))
+                        .push(Text::new("Comparing:"))
+                        .push(Text::new(&package.name).color(get_setting().theme.highlight_text()))
+                        .push(Text::new("(pick another package to compare it with)")),
+                    None => Row::new(),
+                })
+                .push(fetch_errors.iter().fold(
+                    Column::new().spacing(5),
+                    |column, (name, error)| {
+                        column.push(
+                            Row::new()
+                                .spacing(10)
+                                .push(Text::new(format!("Couldn't check {}:", name)))
+                                .push(
+                                    Text::new(format!("{}.", error))
+                                        .color(get_setting().theme.highlight_text()),
+                                ),
+                        )
+                    },
+                )),
         )
         .width(Length::Fill)
         .style(get_setting().theme.info_container())
         .into();
 
-        let packages: Element<'_, GuiMessage> = {
+        let packages: Element<'_, GuiMessage> = if let Some(commit_log) = commit_log {
+            Tab::commit_log_view(commit_log)
+        } else {
             let mut package_count: u16 = 0;
             let filtered_packages = Container::new(
                 packages
@@ -105,7 +139,7 @@ impl<'a> Tab {
                     .fold(Column::new(), |column, package| {
                         package_count += 1;
                         let index = package.index;
-                        let element = package.view(file_exists, package_count & 1 != 0);
+                        let element = package.view(file_exists, package_count & 1 != 0, profiles);
                         column.push(
                             element
                                 .map(move |message| GuiMessage::PackageMessage((index, message))),
@@ -150,4 +184,91 @@ impl<'a> Tab {
         .style(get_setting().theme.normal_container())
         .into()
     }
+
+    fn commit_log_view(commit_log: &'a CommitLog) -> Element<'a, GuiMessage> {
+        let header = Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(
+                Text::new(format!(
+                    "Commits between {} and {}{}",
+                    commit_log.from,
+                    commit_log.to,
+                    match &commit_log.commits {
+                        Ok(commits) => format!(" ({})", commits.len()),
+                        Err(_) => String::new(),
+                    }
+                ))
+                .color(get_setting().theme.highlight_text())
+                .size(TEXT_SIZE + 4)
+                .width(Length::Fill),
+            )
+            .push(
+                Button::new(Text::new("[X] Close"))
+                    .on_press(GuiMessage::CloseCommitLog)
+                    .style(get_setting().theme),
+            );
+
+        let commits: Element<'_, GuiMessage> = match &commit_log.commits {
+            Ok(commits) if commits.is_empty() => Text::new("No commits between them").into(),
+            Ok(commits) => {
+                let mut commit_count: u16 = 0;
+                commits
+                    .iter()
+                    .fold(Column::new(), |column, commit| {
+                        commit_count += 1;
+                        let details = Column::new()
+                            .spacing(5)
+                            .push(
+                                Row::new()
+                                    .spacing(10)
+                                    .align_items(Alignment::Center)
+                                    .push(
+                                        Text::new(&commit.title)
+                                            .color(get_setting().theme.highlight_text())
+                                            .width(Length::Fill),
+                                    )
+                                    .push(
+                                        Button::new(Text::new(&commit.hash[..12]))
+                                            .on_press(GuiMessage::OpenBrowser(commit.url()))
+                                            .style(get_setting().theme),
+                                    ),
+                            )
+                            .push(
+                                Text::new(format!("{} on {}", commit.author, commit.date))
+                                    .size(TEXT_SIZE - 4),
+                            );
+
+                        column.push(
+                            Container::new(if commit.body.is_empty() {
+                                details
+                            } else {
+                                details.push(Text::new(&commit.body).size(TEXT_SIZE - 2))
+                            })
+                            .width(Length::Fill)
+                            .padding(10)
+                            .style(if commit_count & 1 != 0 {
+                                get_setting().theme.odd_container()
+                            } else {
+                                get_setting().theme.even_container()
+                            }),
+                        )
+                    })
+                    .into()
+            }
+            Err(message) => Text::new(format!("Error: {}.", message)).into(),
+        };
+
+        Container::new(
+            Column::new()
+                .padding(10)
+                .spacing(10)
+                .push(header)
+                .push(Scrollable::new(commits)),
+        )
+        .height(Length::Fill)
+        .width(Length::Fill)
+        .style(get_setting().theme.normal_container())
+        .into()
+    }
 }
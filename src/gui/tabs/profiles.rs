@@ -0,0 +1,183 @@
+use super::Tab;
+use crate::{
+    gui::{extra::ProfileForm, message::GuiMessage},
+    profiles::Profile,
+    settings::{get_setting, TEXT_SIZE},
+};
+use iced::{
+    alignment::Horizontal,
+    pure::{
+        widget::{Button, Column, Container, PickList, Row, Scrollable, Text, TextInput},
+        Element,
+    },
+    Alignment, Length,
+};
+
+impl<'a> Tab {
+    pub fn profiles_body(
+        profiles: &'a [Profile],
+        profile_form: &'a ProfileForm,
+    ) -> Element<'a, GuiMessage> {
+        let button = |label, message: Option<GuiMessage>| {
+            let button = Button::new(Text::new(label).horizontal_alignment(Horizontal::Center))
+                .style(get_setting().theme);
+
+            match message {
+                Some(message) => button.on_press(message),
+                None => button,
+            }
+        };
+
+        let info: Element<'_, GuiMessage> = Container::new(
+            Column::new()
+                .padding(10)
+                .spacing(5)
+                .push(Text::new(
+                    "\
+Profiles keep their own Blender configuration, scripts and data files, which get used instead of \
+Blender's usual ones by any package or recent file that picks them.",
+                ))
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Alignment::Center)
+                        .push(
+                            TextInput::new(
+                                "Profile name",
+                                &profile_form.name,
+                                GuiMessage::ProfileNameChanged,
+                            )
+                            .padding(5)
+                            .width(Length::Fill)
+                            .style(get_setting().theme),
+                        )
+                        .push(button("[N] New", Some(GuiMessage::CreateProfile))),
+                )
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Alignment::Center)
+                        .push(Text::new("Clone from:"))
+                        .push(
+                            PickList::new(
+                                profile_form.blender_configs.clone(),
+                                profile_form.clone_source.clone(),
+                                GuiMessage::BlenderConfigSelected,
+                            )
+                            .placeholder(if profile_form.blender_configs.is_empty() {
+                                "No Blender configurations found"
+                            } else {
+                                "Blender configuration"
+                            })
+                            .width(Length::Fill)
+                            .style(get_setting().theme),
+                        )
+                        .push(button(
+                            "[C] Clone",
+                            if profile_form.clone_source.is_some() {
+                                Some(GuiMessage::CloneProfile)
+                            } else {
+                                None
+                            },
+                        ))
+                        .push(button(
+                            "[D] Clone directory",
+                            Some(GuiMessage::CloneProfileFromDirectory),
+                        )),
+                )
+                .push(match &profile_form.error {
+                    Some(message) => Text::new(format!("Error: {}.", message)),
+                    None => Text::new(""),
+                }),
+        )
+        .width(Length::Fill)
+        .style(get_setting().theme.info_container())
+        .into();
+
+        let profiles_view: Element<'_, GuiMessage> = if profiles.is_empty() {
+            Container::new(Text::new("No profiles").size(TEXT_SIZE * 2))
+                .height(Length::Fill)
+                .width(Length::Fill)
+                .center_x()
+                .center_y()
+                .style(get_setting().theme)
+                .into()
+        } else {
+            let mut profile_count: u16 = 0;
+            let list = profiles
+                .iter()
+                .fold(Column::new(), |column, profile| {
+                    profile_count += 1;
+                    let used_by = get_setting()
+                        .package_profiles
+                        .values()
+                        .filter(|name| **name == profile.name)
+                        .count();
+
+                    column.push(
+                        Container::new(
+                            Column::new()
+                                .spacing(10)
+                                .push(
+                                    Text::new(&profile.name)
+                                        .color(get_setting().theme.highlight_text())
+                                        .size(TEXT_SIZE + 10),
+                                )
+                                .push(
+                                    Row::new()
+                                        .align_items(Alignment::End)
+                                        .push(Text::new("Path: ").size(TEXT_SIZE - 4))
+                                        .push(
+                                            Text::new(profile.path.display().to_string())
+                                                .color(get_setting().theme.highlight_text()),
+                                        ),
+                                )
+                                .push(
+                                    Row::new()
+                                        .align_items(Alignment::Center)
+                                        .push(
+                                            Row::new()
+                                                .width(Length::Fill)
+                                                .align_items(Alignment::End)
+                                                .push(Text::new("Used by: ").size(TEXT_SIZE - 4))
+                                                .push(
+                                                    Text::new(format!(
+                                                        "{} package{}",
+                                                        used_by,
+                                                        if used_by == 1 { "" } else { "s" }
+                                                    ))
+                                                    .color(get_setting().theme.highlight_text()),
+                                                ),
+                                        )
+                                        .push(button(
+                                            "[X] Delete",
+                                            Some(GuiMessage::DeleteProfile(profile.clone())),
+                                        )),
+                                ),
+                        )
+                        .width(Length::Fill)
+                        .padding(10)
+                        .style(if profile_count & 1 != 0 {
+                            get_setting().theme.odd_container()
+                        } else {
+                            get_setting().theme.even_container()
+                        }),
+                    )
+                })
+                .width(Length::Fill);
+
+            Container::new(Scrollable::new(list))
+                .height(Length::Fill)
+                .width(Length::Fill)
+                .style(get_setting().theme.normal_container())
+                .into()
+        };
+
+        Container::new(Column::new().push(info).push(profiles_view))
+            .height(Length::Fill)
+            .width(Length::Fill)
+            .center_x()
+            .style(get_setting().theme.normal_container())
+            .into()
+    }
+}
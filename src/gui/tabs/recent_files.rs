@@ -1,14 +1,18 @@
 use super::Tab;
 use crate::{
-    gui::message::GuiMessage,
+    blend_file::{saved_with, BlendVersion},
+    gui::{extra::ProfileChoice, message::GuiMessage, package::PackageMessage},
+    package::{Package, PackageState},
+    profiles::Profile,
     settings::{get_setting, TEXT_SIZE},
+    version_pin::VersionPin,
 };
 use chrono::{DateTime, Local};
 use derive_deref::{Deref, DerefMut};
 use iced::{
     alignment::Horizontal,
     pure::{
-        widget::{Button, Column, Container, Row, Scrollable, Text},
+        widget::{Button, Column, Container, PickList, Row, Scrollable, Text},
         Element,
     },
     Alignment, Length, Space,
@@ -37,19 +41,26 @@ pub struct RecentFile {
     pub path: PathBuf,
     pub last_opened_with: String,
     pub last_opened_on: DateTime<Local>,
+    /// Takes precedence over the profile of the package it's opened with.
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub saved_with: Option<BlendVersion>,
 }
 
 impl RecentFile {
     pub fn new(path: PathBuf, last_opened_with: String) -> Self {
         Self {
             name: path.file_name().unwrap().to_str().unwrap().to_string(),
+            saved_with: saved_with(&path).ok(),
             path,
             last_opened_with,
             last_opened_on: Local::now(),
+            profile: None,
         }
     }
 
-    pub fn view(&self, is_odd: bool) -> Element<'_, RecentFileMessage> {
+    pub fn view(&self, is_odd: bool, profiles: &[Profile]) -> Element<'_, RecentFileMessage> {
         let name = Row::new().spacing(10).push(
             Text::new(&self.name)
                 .color(get_setting().theme.highlight_text())
@@ -102,6 +113,37 @@ impl RecentFile {
                     ),
             );
 
+        let details = match self.saved_with {
+            Some(version) => details.push(
+                Row::new()
+                    .align_items(Alignment::End)
+                    .push(Text::new("Saved with: ").size(TEXT_SIZE - 4))
+                    .push(
+                        Text::new(version.to_string()).color(get_setting().theme.highlight_text()),
+                    ),
+            ),
+            None => details,
+        };
+
+        let details = if profiles.is_empty() {
+            details
+        } else {
+            details.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(Text::new("Profile: ").size(TEXT_SIZE - 4))
+                    .push(
+                        PickList::new(
+                            ProfileChoice::options(profiles),
+                            Some(ProfileChoice(self.profile.clone())),
+                            RecentFileMessage::ProfileSelected,
+                        )
+                        .style(get_setting().theme),
+                    ),
+            )
+        };
+
         let button = |label, recent_file_message: Option<RecentFileMessage>| {
             let button = Button::new(Text::new(label).horizontal_alignment(Horizontal::Center))
                 .width(Length::Fill)
@@ -132,7 +174,9 @@ impl RecentFile {
 
             let button3 = button2.push(button("[S] Select", Some(RecentFileMessage::Select)));
 
-            button3
+            let button4 = button3.push(button("[R] Render", Some(RecentFileMessage::Render)));
+
+            button4
                 .spacing(10)
                 .push(button("[X] Remove entry", Some(RecentFileMessage::Remove)))
                 .into()
@@ -160,7 +204,10 @@ impl RecentFile {
 impl<'a> Tab {
     pub fn recent_files_body(
         file_path: Option<String>,
+        file_version: Option<(BlendVersion, Option<String>)>,
+        file_pin: Option<(&'a VersionPin, Option<&'a Package>, Option<&'a Package>)>,
         recent_files: &'a [RecentFile],
+        profiles: &'a [Profile],
     ) -> Element<'a, GuiMessage> {
         let button = |label, message: Option<GuiMessage>| {
             let button = Button::new(Text::new(label)).style(get_setting().theme);
@@ -171,66 +218,128 @@ impl<'a> Tab {
             }
         };
 
-        let info: Element<'_, GuiMessage> = Container::new(
-            Column::new()
-                .padding(10)
-                .spacing(5)
-                .push(
-                    Row::new()
-                        .spacing(10)
-                        .align_items(Alignment::Center)
-                        .push(button(
-                            "[=]",
-                            if get_setting().default_package.is_some() {
-                                Some(GuiMessage::OpenBlender(
-                                    get_setting().default_package.clone().unwrap().name,
-                                ))
-                            } else {
-                                None
-                            },
-                        ))
-                        .push(Text::new("Default package:"))
-                        .push(
-                            Text::new(match get_setting().default_package.clone() {
-                                Some(package) => package.name,
-                                None => String::from("not set"),
-                            })
+        let info = Column::new()
+            .padding(10)
+            .spacing(5)
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(button(
+                        "[=]",
+                        if get_setting().default_package.is_some() {
+                            Some(GuiMessage::OpenBlender(
+                                get_setting().default_package.clone().unwrap().name,
+                            ))
+                        } else {
+                            None
+                        },
+                    ))
+                    .push(Text::new("Default package:"))
+                    .push(
+                        Text::new(match get_setting().default_package.clone() {
+                            Some(package) => package.name,
+                            None => String::from("not set"),
+                        })
+                        .color(get_setting().theme.highlight_text()),
+                    ),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(button(
+                        "[+]",
+                        if file_path.is_some() && get_setting().default_package.is_some() {
+                            Some(GuiMessage::OpenBlenderWithFile(
+                                get_setting().default_package.clone().unwrap().name,
+                            ))
+                        } else {
+                            None
+                        },
+                    ))
+                    .push(Text::new("File:"))
+                    .push(
+                        Text::new(match &file_path {
+                            Some(file_path) => file_path,
+                            None => "none",
+                        })
+                        .color(get_setting().theme.highlight_text()),
+                    )
+                    .push(Space::with_width(Length::Fill))
+                    .push(
+                        Button::new(Text::new("Select file"))
+                            .on_press(GuiMessage::SelectFile)
+                            .style(get_setting().theme),
+                    ),
+            );
+
+        // Suggest the package that fits the version the file was saved with.
+        let info = match file_version {
+            Some((version, closest)) => info.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(button(
+                        "[~]",
+                        closest.clone().map(GuiMessage::OpenBlenderWithFile),
+                    ))
+                    .push(Text::new(format!(
+                        "Saved with {}, closest package:",
+                        version
+                    )))
+                    .push(
+                        Text::new(closest.unwrap_or_else(|| String::from("none installed")))
                             .color(get_setting().theme.highlight_text()),
+                    ),
+            ),
+            None => info,
+        };
+
+        // The project's pinned package takes over, or is offered for installing.
+        let info = match file_pin {
+            Some((pin, installed, installable)) => info.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(match (installed, installable) {
+                        (Some(package), _) => button(
+                            "[^]",
+                            Some(GuiMessage::OpenBlenderWithFile(package.name.clone())),
                         ),
-                )
-                .push(
-                    Row::new()
-                        .spacing(10)
-                        .align_items(Alignment::Center)
-                        .push(button(
-                            "[+]",
-                            if file_path.is_some() && get_setting().default_package.is_some() {
-                                Some(GuiMessage::OpenBlenderWithFile(
-                                    get_setting().default_package.clone().unwrap().name,
-                                ))
-                            } else {
-                                None
-                            },
-                        ))
-                        .push(Text::new("File:"))
-                        .push(
-                            Text::new(match &file_path {
-                                Some(file_path) => file_path,
-                                None => "none",
-                            })
-                            .color(get_setting().theme.highlight_text()),
-                        )
-                        .push(Space::with_width(Length::Fill))
-                        .push(
-                            Button::new(Text::new("Select file"))
-                                .on_press(GuiMessage::SelectFile)
-                                .style(get_setting().theme),
+                        (None, Some(package)) => button(
+                            "[#]",
+                            Some(GuiMessage::PackageMessage((
+                                package.index,
+                                PackageMessage::Install,
+                            ))),
                         ),
-                ),
-        )
-        .width(Length::Fill)
-        .style(get_setting().theme.info_container())
-        .into();
+                        (None, None) => button("[^]", None),
+                    })
+                    .push(Text::new(format!("Pinned to '{}':", pin.query)))
+                    .push(
+                        Text::new(match (installed, installable) {
+                            (Some(package), _) => package.name.clone(),
+                            (None, Some(package)) => match package.state {
+                                PackageState::Fetched | PackageState::Errored { .. } => {
+                                    format!("install {}", package.name)
+                                }
+                                _ => format!("installing {}", package.name),
+                            },
+                            (None, None) => String::from("no such package found"),
+                        })
+                        .color(get_setting().theme.highlight_text()),
+                    )
+                    .push(Space::with_width(Length::Fill))
+                    .push(Text::new(pin.file.display().to_string()).size(TEXT_SIZE - 4)),
+            ),
+            None => info,
+        };
+
+        let info: Element<'_, GuiMessage> = Container::new(info)
+            .width(Length::Fill)
+            .style(get_setting().theme.info_container())
+            .into();
 
         let recent_files_view: Element<'_, GuiMessage> = {
             let mut file_count: u16 = 0;
@@ -240,7 +349,7 @@ impl<'a> Tab {
                     .fold(Column::new(), |column, recent_file| {
                         file_count += 1;
                         let path = recent_file.path.to_str().unwrap().to_string();
-                        let element = recent_file.view(file_count & 1 != 0);
+                        let element = recent_file.view(file_count & 1 != 0, profiles);
                         column.push(element.map(move |message| {
                             GuiMessage::RecentFileMessage((path.clone(), message))
                         }))
@@ -280,4 +389,6 @@ pub enum RecentFileMessage {
     OpenWithDefaultBlender,
     Select,
     Remove,
+    Render,
+    ProfileSelected(ProfileChoice),
 }
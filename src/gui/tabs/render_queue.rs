@@ -0,0 +1,254 @@
+use super::Tab;
+use crate::{
+    gui::{
+        extra::{ActiveRender, BlendFile, RenderForm},
+        message::GuiMessage,
+    },
+    renderer::{JobState, RenderJob},
+    settings::{get_setting, TEXT_SIZE},
+};
+use iced::{
+    alignment::Horizontal,
+    pure::{
+        widget::{Button, Column, Container, PickList, Row, Scrollable, Text, TextInput},
+        Element,
+    },
+    Alignment, Length, ProgressBar, Space,
+};
+
+impl<'a> Tab {
+    pub fn render_queue_body(
+        render_queue: &'a [RenderJob],
+        render: &'a Option<ActiveRender>,
+        running: bool,
+        render_form: &'a RenderForm,
+        files: Vec<BlendFile>,
+        packages: Vec<String>,
+    ) -> Element<'a, GuiMessage> {
+        let button = |label, message: Option<GuiMessage>| {
+            let button = Button::new(Text::new(label).horizontal_alignment(Horizontal::Center))
+                .style(get_setting().theme);
+
+            match message {
+                Some(message) => button.on_press(message),
+                None => button,
+            }
+        };
+
+        let frame_input = |placeholder, value, message: fn(String) -> GuiMessage| {
+            TextInput::new(placeholder, value, message)
+                .padding(5)
+                .width(Length::Units(80))
+                .style(get_setting().theme)
+        };
+
+        let info: Element<'_, GuiMessage> = Container::new(
+            Column::new()
+                .padding(10)
+                .spacing(5)
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Alignment::Center)
+                        .push(Text::new("File:"))
+                        .push(
+                            PickList::new(
+                                files,
+                                render_form.file.clone(),
+                                GuiMessage::RenderFileSelected,
+                            )
+                            .placeholder("Recent file")
+                            .width(Length::Fill)
+                            .style(get_setting().theme),
+                        )
+                        .push(button("Select file", Some(GuiMessage::SelectRenderFile))),
+                )
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Alignment::Center)
+                        .push(Text::new("Package:"))
+                        .push(
+                            PickList::new(
+                                packages,
+                                render_form.package.clone(),
+                                GuiMessage::RenderPackageSelected,
+                            )
+                            .placeholder("Installed package")
+                            .width(Length::Fill)
+                            .style(get_setting().theme),
+                        )
+                        .push(Text::new("Frames:"))
+                        .push(frame_input(
+                            "start",
+                            &render_form.start,
+                            GuiMessage::RenderStartChanged,
+                        ))
+                        .push(Text::new("to"))
+                        .push(frame_input(
+                            "end",
+                            &render_form.end,
+                            GuiMessage::RenderEndChanged,
+                        ))
+                        .push(button("[+] Queue", Some(GuiMessage::QueueRender))),
+                )
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Alignment::Center)
+                        .push(
+                            Text::new(match &render_form.error {
+                                Some(message) => format!("Error: {}.", message),
+                                None => String::from(
+                                    "Leaving the frames empty renders the file's own frame range.",
+                                ),
+                            })
+                            .width(Length::Fill),
+                        )
+                        .push(if running {
+                            button("[||] Stop queue", Some(GuiMessage::StopRenderQueue))
+                        } else {
+                            button(
+                                "[>] Start queue",
+                                if render_queue.iter().any(|job| job.state == JobState::Queued) {
+                                    Some(GuiMessage::StartRenderQueue)
+                                } else {
+                                    None
+                                },
+                            )
+                        }),
+                ),
+        )
+        .width(Length::Fill)
+        .style(get_setting().theme.info_container())
+        .into();
+
+        let render_queue_view: Element<'_, GuiMessage> = if render_queue.is_empty() {
+            Container::new(Text::new("No renders queued").size(TEXT_SIZE * 2))
+                .height(Length::Fill)
+                .width(Length::Fill)
+                .center_x()
+                .center_y()
+                .style(get_setting().theme)
+                .into()
+        } else {
+            let mut job_count: u16 = 0;
+            let list = render_queue
+                .iter()
+                .fold(Column::new(), |column, job| {
+                    job_count += 1;
+                    let active = render.as_ref().filter(|render| render.job.id == job.id);
+
+                    let detail = |label, value: String| {
+                        Row::new()
+                            .align_items(Alignment::End)
+                            .push(Text::new(label).size(TEXT_SIZE - 4))
+                            .push(Text::new(value).color(get_setting().theme.highlight_text()))
+                    };
+
+                    let state: Element<'_, GuiMessage> = match (active, &job.state) {
+                        (Some(active), _) => {
+                            let status = match &active.status {
+                                Some(status) => {
+                                    let mut text = format!("Frame {}", status.frame);
+                                    if let Some((done, total)) = status.samples {
+                                        text.push_str(&format!(" | Sample {}/{}", done, total));
+                                    }
+                                    if let Some(remaining) = &status.remaining {
+                                        text.push_str(&format!(" | Remaining {}", remaining));
+                                    }
+                                    text
+                                }
+                                None => String::from("Starting..."),
+                            };
+
+                            Row::new()
+                                .spacing(10)
+                                .align_items(Alignment::Center)
+                                .push(Text::new(status))
+                                .push(
+                                    ProgressBar::new(0.0..=100.0, active.progress())
+                                        .width(Length::Fill)
+                                        .style(get_setting().theme),
+                                )
+                                .push(Text::new(format!(
+                                    "{} frame{} done",
+                                    active.frames_saved,
+                                    if active.frames_saved == 1 { "" } else { "s" }
+                                )))
+                                .into()
+                        }
+                        (None, JobState::Queued) => {
+                            detail("State: ", String::from("Queued")).into()
+                        }
+                        (None, JobState::Finished) => {
+                            detail("State: ", String::from("Finished")).into()
+                        }
+                        (None, JobState::Failed(message)) => {
+                            detail("State: ", format!("Failed: {}.", message)).into()
+                        }
+                    };
+
+                    let controls = Row::new()
+                        .spacing(10)
+                        .push(Space::with_width(Length::Fill))
+                        .push(button(
+                            "[R] Requeue",
+                            if job.state != JobState::Queued {
+                                Some(GuiMessage::RequeueRenderJob(job.id))
+                            } else {
+                                None
+                            },
+                        ))
+                        .push(button(
+                            if active.is_some() {
+                                "[X] Cancel"
+                            } else {
+                                "[X] Remove"
+                            },
+                            Some(GuiMessage::RemoveRenderJob(job.id)),
+                        ));
+
+                    column.push(
+                        Container::new(
+                            Column::new()
+                                .spacing(10)
+                                .push(
+                                    Text::new(
+                                        job.file.file_name().unwrap_or_default().to_string_lossy(),
+                                    )
+                                    .color(get_setting().theme.highlight_text())
+                                    .size(TEXT_SIZE + 10),
+                                )
+                                .push(detail("Path: ", job.file.display().to_string()))
+                                .push(detail("Package: ", job.package.clone()))
+                                .push(detail("Frames: ", job.frames.to_string()))
+                                .push(state)
+                                .push(controls),
+                        )
+                        .width(Length::Fill)
+                        .padding(10)
+                        .style(if job_count & 1 != 0 {
+                            get_setting().theme.odd_container()
+                        } else {
+                            get_setting().theme.even_container()
+                        }),
+                    )
+                })
+                .width(Length::Fill);
+
+            Container::new(Scrollable::new(list))
+                .height(Length::Fill)
+                .width(Length::Fill)
+                .style(get_setting().theme.normal_container())
+                .into()
+        };
+
+        Container::new(Column::new().push(info).push(render_queue_view))
+            .height(Length::Fill)
+            .width(Length::Fill)
+            .center_x()
+            .style(get_setting().theme.normal_container())
+            .into()
+    }
+}
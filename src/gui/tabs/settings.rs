@@ -1,29 +1,36 @@
 use super::Tab;
 use crate::{
+    association::matching_rule,
     gui::{
-        extra::{BuildTypeSettings, Choice, DiskSpace, Location},
+        extra::{
+            AssociationRuleForm, BuildTypeSettings, Choice, CustomPackageForm, DiskSpace, Location,
+        },
         message::GuiMessage,
         style::Theme,
     },
-    package::Build,
+    package::{Arch, Build, Os, Target},
     releases::{ReleaseType, Releases},
     settings::{get_setting, ModifierKey, CONFIG_FILE_ENV, PORTABLE, PROJECT_DIRS, TEXT_SIZE},
 };
 use iced::{
     alignment::Horizontal,
     pure::{
-        widget::{Button, Column, Container, Radio, Row, Scrollable, Text},
+        widget::{Button, Column, Container, Radio, Row, Scrollable, Text, TextInput},
         Element,
     },
     Alignment, Length, Rule, Space,
 };
-use std::sync::atomic::Ordering;
+use std::{path::Path, sync::atomic::Ordering};
 
 impl Tab {
-    pub fn settings_body(
-        releases: &Releases,
+    pub fn settings_body<'a>(
+        releases: &'a Releases,
         disk_space: Option<DiskSpace>,
-    ) -> Element<'_, GuiMessage> {
+        custom_package: &'a CustomPackageForm,
+        association_rule: &'a AssociationRuleForm,
+        file_path: Option<&'a str>,
+        mirror_input: &'a str,
+    ) -> Element<'a, GuiMessage> {
         let settings_block_intro = |title, description| {
             Column::new()
                 .spacing(10)
@@ -331,6 +338,28 @@ can be higher) when installing an update.",
             GuiMessage::UseLatestAsDefault,
         );
 
+        let open_with_closest_package = choice_setting!(
+            "Open files with the closest package",
+            "\
+When bypassing the launcher, open files with the installed package closest to the version of \
+Blender they were saved with instead of the default package. That's the newest package of the \
+same version, or else the oldest newer one.",
+            &Choice::ALL,
+            Some(choice(get_setting().open_with_closest_package).unwrap()),
+            GuiMessage::OpenWithClosestPackage,
+        );
+
+        let back_up_risky_files = choice_setting!(
+            "Back up files before risky openings",
+            "\
+Opening a file with a package older than the Blender it was saved with, or with a newer daily or \
+experimental build, asks for confirmation first. With this enabled, a copy of the file with the \
+current time in its name is made next to it as well.",
+            &Choice::ALL,
+            Some(choice(get_setting().back_up_risky_files).unwrap()),
+            GuiMessage::BackUpRiskyFiles,
+        );
+
         let choose_theme = choice_setting!(
             "Choose the theme",
             "Both try to mimic Blender's colour schemes as much as possible.",
@@ -416,6 +445,420 @@ whatever its name is.",
             )
             .push(Space::with_width(Length::Units(10)));
 
+        let custom_packages = Row::new()
+            .align_items(Alignment::Center)
+            .push(Space::with_width(Length::Units(10)))
+            .push(
+                Column::new()
+                    .spacing(10)
+                    .width(Length::Fill)
+                    .push(
+                        Text::new("Custom packages")
+                            .color(get_setting().theme.highlight_text())
+                            .size(TEXT_SIZE * 2),
+                    )
+                    .push(Text::new(
+                        "\
+Register a Blender that wasn't installed through Ablavema, like a self-compiled one, so it can be \
+launched and set as default like any other package. Its files are never removed by Ablavema, \
+uninstalling it only unregisters it.",
+                    ))
+                    .push(
+                        Row::new()
+                            .spacing(5)
+                            .push(
+                                TextInput::new(
+                                    "Name",
+                                    &custom_package.name,
+                                    GuiMessage::CustomPackageNameChanged,
+                                )
+                                .padding(5)
+                                .width(Length::FillPortion(2))
+                                .style(get_setting().theme),
+                            )
+                            .push(
+                                TextInput::new(
+                                    "Version",
+                                    &custom_package.version,
+                                    GuiMessage::CustomPackageVersionChanged,
+                                )
+                                .padding(5)
+                                .width(Length::FillPortion(1))
+                                .style(get_setting().theme),
+                            ),
+                    )
+                    .push(
+                        TextInput::new(
+                            "Label (optional)",
+                            &custom_package.label,
+                            GuiMessage::CustomPackageLabelChanged,
+                        )
+                        .padding(5)
+                        .style(get_setting().theme),
+                    )
+                    .push(Text::new(match &custom_package.path {
+                        Some(path) => format!("Location: {}", path.display()),
+                        None => String::from("Location: none selected"),
+                    }))
+                    .push(
+                        Row::new()
+                            .spacing(5)
+                            .push(
+                                Button::new(
+                                    Text::new("Directory").horizontal_alignment(Horizontal::Center),
+                                )
+                                .on_press(GuiMessage::PickCustomPackageDirectory)
+                                .width(Length::Fill)
+                                .style(get_setting().theme.tab_button()),
+                            )
+                            .push(
+                                Button::new(
+                                    Text::new("Executable")
+                                        .horizontal_alignment(Horizontal::Center),
+                                )
+                                .on_press(GuiMessage::PickCustomPackageExecutable)
+                                .width(Length::Fill)
+                                .style(get_setting().theme.tab_button()),
+                            )
+                            .push(Space::with_width(Length::Units(15)))
+                            .push({
+                                let button = Button::new(
+                                    Text::new("Register").horizontal_alignment(Horizontal::Center),
+                                )
+                                .width(Length::Fill)
+                                .style(get_setting().theme.tab_button());
+
+                                if custom_package.path.is_some() {
+                                    button.on_press(GuiMessage::RegisterCustomPackage)
+                                } else {
+                                    button
+                                }
+                            }),
+                    )
+                    .push(match &custom_package.error {
+                        Some(message) => Text::new(format!("Error: {}.", message)),
+                        None => Text::new(""),
+                    }),
+            )
+            .push(Space::with_width(Length::Units(10)));
+
+        let matched_rule = file_path.and_then(|file_path| {
+            matching_rule(&get_setting().association_rules, Path::new(file_path))
+                .map(|(index, _)| index)
+        });
+
+        let association_rules = Row::new()
+            .align_items(Alignment::Center)
+            .push(Space::with_width(Length::Units(10)))
+            .push(
+                Column::new()
+                    .spacing(10)
+                    .width(Length::Fill)
+                    .push(
+                        Text::new("Association rules")
+                            .color(get_setting().theme.highlight_text())
+                            .size(TEXT_SIZE * 2),
+                    )
+                    .push(Text::new(
+                        "\
+When bypassing the launcher, open the files whose path matches a rule with the newest installed \
+package its query picks, like \"3.6\", \"lts\" or \"daily:main\", instead of the default \
+package. The first matching rule is used. In patterns, \"*\" matches within a directory, \"**\" \
+any number of them and \"~\" is the home directory, like \"~/projects/studio_a/**\". Version files \
+of projects still take precedence.",
+                    ))
+                    .push(Text::new(match (file_path, matched_rule) {
+                        (Some(file_path), Some(index)) => {
+                            format!("'{}' is matched by rule {}.", file_path, index + 1)
+                        }
+                        (Some(file_path), None) => {
+                            format!("'{}' isn't matched by any rule.", file_path)
+                        }
+                        (None, _) => String::from("No file selected."),
+                    }))
+                    .push(get_setting().association_rules.iter().enumerate().fold(
+                        Column::new().spacing(5),
+                        |column, (index, rule)| {
+                            let button = |label, message: Option<GuiMessage>| {
+                                let button = Button::new(Text::new(label))
+                                    .style(get_setting().theme.tab_button());
+
+                                match message {
+                                    Some(message) => button.on_press(message),
+                                    None => button,
+                                }
+                            };
+                            let text = |text: String| {
+                                let text = Text::new(text).width(Length::Fill);
+                                if matched_rule == Some(index) {
+                                    text.color(get_setting().theme.highlight_text())
+                                } else {
+                                    text
+                                }
+                            };
+
+                            column.push(
+                                Row::new()
+                                    .spacing(10)
+                                    .align_items(Alignment::Center)
+                                    .push(Text::new(if matched_rule == Some(index) {
+                                        format!("[>] {}.", index + 1)
+                                    } else {
+                                        format!("{}.", index + 1)
+                                    }))
+                                    .push(text(rule.pattern.clone()))
+                                    .push(text(rule.query.to_string()))
+                                    .push(text(match &rule.profile {
+                                        Some(profile) => profile.clone(),
+                                        None => String::from("Default profile"),
+                                    }))
+                                    .push(button(
+                                        "[^]",
+                                        (index > 0)
+                                            .then_some(GuiMessage::MoveAssociationRuleUp(index)),
+                                    ))
+                                    .push(button(
+                                        "[v]",
+                                        (index + 1 < get_setting().association_rules.len())
+                                            .then_some(GuiMessage::MoveAssociationRuleDown(index)),
+                                    ))
+                                    .push(button(
+                                        "[X]",
+                                        Some(GuiMessage::RemoveAssociationRule(index)),
+                                    )),
+                            )
+                        },
+                    ))
+                    .push(
+                        Row::new()
+                            .spacing(5)
+                            .push(
+                                TextInput::new(
+                                    "~/projects/studio_a/**",
+                                    &association_rule.pattern,
+                                    GuiMessage::AssociationPatternChanged,
+                                )
+                                .on_submit(GuiMessage::AddAssociationRule)
+                                .padding(5)
+                                .width(Length::FillPortion(3))
+                                .style(get_setting().theme),
+                            )
+                            .push(
+                                TextInput::new(
+                                    "Query",
+                                    &association_rule.query,
+                                    GuiMessage::AssociationQueryChanged,
+                                )
+                                .on_submit(GuiMessage::AddAssociationRule)
+                                .padding(5)
+                                .width(Length::FillPortion(1))
+                                .style(get_setting().theme),
+                            )
+                            .push(
+                                TextInput::new(
+                                    "Profile (optional)",
+                                    &association_rule.profile,
+                                    GuiMessage::AssociationProfileChanged,
+                                )
+                                .on_submit(GuiMessage::AddAssociationRule)
+                                .padding(5)
+                                .width(Length::FillPortion(1))
+                                .style(get_setting().theme),
+                            )
+                            .push(
+                                Button::new(
+                                    Text::new("Add").horizontal_alignment(Horizontal::Center),
+                                )
+                                .on_press(GuiMessage::AddAssociationRule)
+                                .width(Length::Units(100))
+                                .style(get_setting().theme.tab_button()),
+                            ),
+                    )
+                    .push(match &association_rule.error {
+                        Some(message) => Text::new(format!("Error: {}.", message)),
+                        None => Text::new(""),
+                    }),
+            )
+            .push(Space::with_width(Length::Units(10)));
+
+        let mirrors = Row::new()
+            .align_items(Alignment::Center)
+            .push(Space::with_width(Length::Units(10)))
+            .push(
+                Column::new()
+                    .spacing(10)
+                    .width(Length::Fill)
+                    .push(
+                        Text::new("Mirrors")
+                            .color(get_setting().theme.highlight_text())
+                            .size(TEXT_SIZE * 2),
+                    )
+                    .push(Text::new(
+                        "\
+Stable and LTS packages are downloaded from these mirrors of Blender's release directory. They're \
+tried in order, except that the ones that failed recently are left for last.",
+                    ))
+                    .push(get_setting().mirrors.iter().fold(
+                        Column::new().spacing(5),
+                        |column, mirror| {
+                            column.push(
+                                Row::new()
+                                    .spacing(10)
+                                    .align_items(Alignment::Center)
+                                    .push(Text::new(&mirror.url).width(Length::Fill))
+                                    .push(Text::new(match mirror.recent_failures() {
+                                        0 => String::from("Healthy"),
+                                        1 => String::from("1 failure"),
+                                        failures => format!("{} failures", failures),
+                                    }))
+                                    .push({
+                                        let button = Button::new(Text::new("[X]"))
+                                            .style(get_setting().theme.tab_button());
+
+                                        if get_setting().mirrors.len() > 1 {
+                                            button.on_press(GuiMessage::RemoveMirror(
+                                                mirror.url.clone(),
+                                            ))
+                                        } else {
+                                            button
+                                        }
+                                    }),
+                            )
+                        },
+                    ))
+                    .push(
+                        Row::new()
+                            .spacing(5)
+                            .push(
+                                TextInput::new(
+                                    "https://example.org/blender/release/",
+                                    mirror_input,
+                                    GuiMessage::MirrorInputChanged,
+                                )
+                                .on_submit(GuiMessage::AddMirror)
+                                .padding(5)
+                                .width(Length::Fill)
+                                .style(get_setting().theme),
+                            )
+                            .push(
+                                Button::new(
+                                    Text::new("Add").horizontal_alignment(Horizontal::Center),
+                                )
+                                .on_press(GuiMessage::AddMirror)
+                                .width(Length::Units(100))
+                                .style(get_setting().theme.tab_button()),
+                            )
+                            .push(
+                                Button::new(
+                                    Text::new("Reset").horizontal_alignment(Horizontal::Center),
+                                )
+                                .on_press(GuiMessage::ResetMirrors)
+                                .width(Length::Units(100))
+                                .style(get_setting().theme.tab_button()),
+                            ),
+                    ),
+            )
+            .push(Space::with_width(Length::Units(10)));
+
+        let targets =
+            Row::new()
+                .align_items(Alignment::Center)
+                .push(Space::with_width(Length::Units(10)))
+                .push(
+                    Column::new()
+                        .spacing(10)
+                        .width(Length::Fill)
+                        .push(
+                            Text::new("Targets")
+                                .color(get_setting().theme.highlight_text())
+                                .size(TEXT_SIZE * 2),
+                        )
+                        .push(Text::new(
+                            "\
+Packages are fetched for the operating systems and architectures chosen here, which takes effect \
+the next time they're checked for. Packages for other machines can be installed to share them, \
+but only those built for this one can be launched.",
+                        ))
+                        .push(
+                            Os::ALL
+                                .into_iter()
+                                .fold(Column::new().spacing(5), |column, os| {
+                                    column.push(Arch::ALL.into_iter().fold(
+                                        Row::new().spacing(10).align_items(Alignment::Center).push(
+                                            Text::new(os.to_string()).width(Length::Units(110)),
+                                        ),
+                                        |row, arch| {
+                                            let target = Target { os, arch };
+                                            let label = if target.is_tracked() {
+                                                format!("[X] {}", arch)
+                                            } else {
+                                                format!("[ ] {}", arch)
+                                            };
+                                            row.push(
+                                                Button::new(
+                                                    Text::new(label)
+                                                        .horizontal_alignment(Horizontal::Center),
+                                                )
+                                                .on_press(GuiMessage::ToggleTarget(target))
+                                                .width(Length::Units(110))
+                                                .style(get_setting().theme.tab_button()),
+                                            )
+                                        },
+                                    ))
+                                }),
+                        ),
+                )
+                .push(Space::with_width(Length::Units(10)));
+
+        let blender_repository = Row::new()
+            .align_items(Alignment::Center)
+            .push(Space::with_width(Length::Units(10)))
+            .push(
+                Column::new()
+                    .spacing(10)
+                    .width(Length::Fill)
+                    .push(
+                        Text::new("Blender repository")
+                            .color(get_setting().theme.highlight_text())
+                            .size(TEXT_SIZE * 2),
+                    )
+                    .push(Text::new(
+                        "\
+A local clone of Blender's git repository, which is used for showing the commits between two \
+packages. Keep it up to date by pulling regularly, since newer builds won't be found otherwise.",
+                    ))
+                    .push(Text::new(match &get_setting().blender_repository {
+                        Some(path) => format!("Location: {}", path.display()),
+                        None => String::from("Location: not set"),
+                    }))
+                    .push(
+                        Row::new()
+                            .spacing(5)
+                            .push(
+                                Button::new(
+                                    Text::new("Select").horizontal_alignment(Horizontal::Center),
+                                )
+                                .on_press(GuiMessage::SelectBlenderRepository)
+                                .width(Length::Units(100))
+                                .style(get_setting().theme.tab_button()),
+                            )
+                            .push({
+                                let button = Button::new(
+                                    Text::new("Unset").horizontal_alignment(Horizontal::Center),
+                                )
+                                .width(Length::Units(100))
+                                .style(get_setting().theme.tab_button());
+
+                                if get_setting().blender_repository.is_some() {
+                                    button.on_press(GuiMessage::UnsetBlenderRepository)
+                                } else {
+                                    button
+                                }
+                            }),
+                    ),
+            )
+            .push(Space::with_width(Length::Units(10)));
+
         let remove_databases = Row::new()
             .align_items(Alignment::Center)
             .push(Space::with_width(Length::Units(10)))
@@ -660,10 +1103,24 @@ to see if a bug was there before or whatnot.",
             .push(separator())
             .push(use_latest_as_default)
             .push(separator())
+            .push(open_with_closest_package)
+            .push(separator())
+            .push(back_up_risky_files)
+            .push(separator())
+            .push(association_rules)
+            .push(separator())
             .push(choose_theme)
             .push(separator())
             .push(change_location)
             .push(separator())
+            .push(custom_packages)
+            .push(separator())
+            .push(blender_repository)
+            .push(separator())
+            .push(mirrors)
+            .push(separator())
+            .push(targets)
+            .push(separator())
             .push(remove_databases)
             .push(separator())
             .push(remove_packages)
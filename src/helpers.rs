@@ -1,47 +1,72 @@
-use crate::settings::{get_setting, CAN_CONNECT};
-use reqwest::{self, ClientBuilder};
+use crate::{
+    association::matching_rule,
+    blend_file::{back_up, opening_risk, saved_with},
+    error::{AblavemaError, OrParseError, Result},
+    http, mirrors,
+    package::Package,
+    profiles::Profile,
+    settings::{get_setting, BASE_URL_ENV, CAN_CONNECT},
+    version_pin::VersionPin,
+};
+use native_dialog::{MessageDialog, MessageType};
 use select::document::Document;
-use std::{path::Path, process::Command, sync::atomic::Ordering, time::Duration};
+use std::{env::var, path::Path, process::Command, sync::atomic::Ordering, time::Duration};
 use tokio::{join, time::sleep};
 
+/// Stops a task reporting its progress through `$sender` if nobody is listening anymore, which
+/// is how cancelling installations and renders works. Returns the default of whatever the task
+/// returns, like `None` or `()`.
+macro_rules! send_or_return {
+    ($sender:expr, $progress:expr) => {
+        if $sender.send($progress).is_err() {
+            return Default::default();
+        }
+    };
+}
+pub(crate) use send_or_return;
+
 /// Check whether there's a working connection to the download servers.
 pub async fn check_connection() {
     let urls = [
         "https://builder.blender.org/download/",
         "https://www.blender.org/download/",
-        "https://ftp.nluug.nl/pub/graphics/blender/release/",
+        &mirrors::ranked()[0],
         "https://github.com/AlexChaplinBraz/Ablavema",
     ];
 
-    let client = ClientBuilder::new()
-        .connect_timeout(Duration::from_secs(1))
-        .build()
-        .unwrap();
+    // Only whether the servers answer matters here, so there's no point in waiting long.
+    let check = |url| async move {
+        // Keeping just the status gives the slots of the requests back right away.
+        let result = http::send(http::client().get(url).timeout(Duration::from_secs(5)))
+            .await
+            .map(|response| response.status());
+        (url, result)
+    };
 
     let (r0, r1, r2, r3) = join!(
-        client.get(&*urls[0]).send(),
-        client.get(&*urls[1]).send(),
-        client.get(&*urls[2]).send(),
-        client.get(&*urls[3]).send()
+        check(urls[0]),
+        check(urls[1]),
+        check(urls[2]),
+        check(urls[3])
     );
 
     let mut failed_urls = Vec::new();
 
-    for result in [r0, r1, r2, r3] {
+    for (url, result) in [r0, r1, r2, r3] {
         match result {
-            Ok(response) => {
-                let url = response.url().to_string();
-
-                if response.status().is_client_error() {
+            Ok(status) => {
+                if status.is_client_error() {
                     eprintln!("Client error connecting to '{}'.", url);
                     failed_urls.push(url);
-                } else if response.status().is_server_error() {
+                } else if status.is_server_error() {
                     eprintln!("Server error connecting to '{}'.", url);
                     failed_urls.push(url);
                 }
             }
+            // The server is there, it just doesn't want to be bothered for a while,
+            // which is reported by whatever tries to use it.
+            Err(AblavemaError::RateLimited { .. }) => (),
             Err(e) => {
-                let url = e.url().unwrap().to_string();
                 eprintln!("Error connecting to '{}'.\nThe error was: {}", url, e);
                 failed_urls.push(url);
             }
@@ -59,18 +84,19 @@ pub async fn check_connection() {
     sleep(Duration::from_secs(1)).await;
 
     for url in failed_urls {
-        match client.get(&url).send().await {
-            Ok(response) => {
-                if response.status().is_client_error() {
+        match check(url).await.1 {
+            Ok(status) => {
+                if status.is_client_error() {
                     eprintln!("Client error connecting to '{}'.", url);
                     CAN_CONNECT.store(false, Ordering::Relaxed);
                     return;
-                } else if response.status().is_server_error() {
+                } else if status.is_server_error() {
                     eprintln!("Server error connecting to '{}'.", url);
                     CAN_CONNECT.store(false, Ordering::Relaxed);
                     return;
                 }
             }
+            Err(AblavemaError::RateLimited { .. }) => (),
             Err(e) => {
                 eprintln!("Error connecting to '{}'.\nThe error was: {}", url, e);
                 CAN_CONNECT.store(false, Ordering::Relaxed);
@@ -82,28 +108,110 @@ pub async fn check_connection() {
     CAN_CONNECT.store(true, Ordering::Relaxed);
 }
 
-pub async fn get_document(url: &str) -> Document {
-    // TODO: Fix hang on getting temp banned mid fetching.
-    // Should be resolved by adding a timeout, but the requirement is being
-    // able to pass an error around and handle it.
-    let resp = reqwest::get(url).await.unwrap();
-    assert!(resp.status().is_success());
-    let resp = resp.bytes().await.unwrap();
-    Document::from_read(&resp[..]).unwrap()
+/// URL of a page on one of Blender's sites, like `site_url("www.blender.org", "download/")`.
+/// Setting `BASE_URL_ENV` serves every site from under that URL instead, by host,
+/// which is how the scrapers are pointed at a local stand-in with recorded pages.
+pub fn site_url(host: &str, path: &str) -> String {
+    match var(BASE_URL_ENV) {
+        Ok(base) => format!("{}/{}/{}", base.trim_end_matches('/'), host, path),
+        Err(_) => format!("https://{}/{}", host, path),
+    }
+}
+
+pub async fn get_document(url: &str) -> Result<Document> {
+    let resp = http::get_bytes(url).await?;
+    Document::from_read(&resp[..]).or_parse_error(url, "not a readable page")
 }
 
-pub fn open_blender(package: String, file_path: Option<String>) {
-    let mut cmd = Command::new(get_setting().packages_dir.join(package).join({
-        if cfg!(target_os = "linux") {
-            "blender"
-        } else if cfg!(target_os = "windows") {
-            "blender.exe"
-        } else if cfg!(target_os = "macos") {
-            todo!("macos executable");
+/// The command for launching a package, with the profile the file or package is meant to use,
+/// where the one pinned by the file's project comes first.
+/// The file itself isn't passed as an argument.
+pub fn blender_command(package: &str, file_path: Option<&Path>) -> Command {
+    let custom_package = get_setting()
+        .custom_packages
+        .iter()
+        .find(|custom_package| custom_package.name == package)
+        .cloned();
+    let mut cmd = Command::new(match custom_package {
+        Some(custom_package) => custom_package.executable(),
+        None => get_setting()
+            .packages_dir
+            .join(package)
+            .join(get_executable_name()),
+    });
+    let profile = file_path
+        .and_then(|path| VersionPin::find(path)?.profile)
+        .or_else(|| {
+            matching_rule(&get_setting().association_rules, file_path?)?
+                .1
+                .profile
+                .clone()
+        })
+        .or_else(|| get_setting().recent_files.get(file_path?)?.profile.clone())
+        .or_else(|| get_setting().package_profiles.get(package).cloned());
+    if let Some(profile) = profile {
+        match Profile::get(&profile) {
+            Some(profile) => profile.apply(&mut cmd),
+            None => eprintln!("Profile '{}' not found, using the default one.", profile),
+        }
+    }
+    cmd
+}
+
+/// Ask before opening a file with a package that may lose data when saving it, making a backup
+/// first if enabled. Returns whether the file should be opened.
+pub fn confirm_opening(file: &Path, package: &Package) -> bool {
+    let risk = match saved_with(file) {
+        Ok(version) => match opening_risk(version, package) {
+            Some(risk) => risk,
+            None => return true,
+        },
+        Err(_) => return true,
+    };
+    let back_up_file = get_setting().back_up_risky_files;
+
+    let text = format!(
+        "{}\n\n{}Open it anyway?",
+        risk,
+        if back_up_file {
+            "A copy of the file will be made next to it before opening it. "
         } else {
-            unreachable!("Unsupported OS");
+            ""
+        }
+    );
+    let confirmed = match MessageDialog::new()
+        .set_type(MessageType::Warning)
+        .set_title("Ablavema")
+        .set_text(&text)
+        .show_confirm()
+    {
+        Ok(confirmed) => confirmed,
+        Err(_) => {
+            // Without a dialog there's nobody to ask, so the file is opened as before.
+            #[cfg(target_os = "linux")]
+            println!(
+                "Warning: {}\nProbably need to install 'zenity' or 'kdialog' for a graphical dialog.",
+                risk
+            );
+            true
+        }
+    };
+
+    if confirmed && back_up_file {
+        match back_up(file) {
+            Ok(backup) => println!("Backed up '{}' to '{}'.", file.display(), backup.display()),
+            Err(e) => {
+                eprintln!("Failed to back up '{}': {}", file.display(), e);
+                return false;
+            }
         }
-    }));
+    }
+
+    confirmed
+}
+
+pub fn open_blender(package: String, file_path: Option<String>) {
+    let mut cmd = blender_command(&package, file_path.as_deref().map(Path::new));
     if let Some(path) = file_path {
         cmd.arg(path);
     }
@@ -137,6 +245,46 @@ pub fn open_blender(package: String, file_path: Option<String>) {
     cmd.spawn().unwrap();
 }
 
+pub fn get_executable_name() -> &'static str {
+    if cfg!(target_os = "linux") {
+        "blender"
+    } else if cfg!(target_os = "windows") {
+        "blender.exe"
+    } else if cfg!(target_os = "macos") {
+        todo!("macos executable");
+    } else {
+        unreachable!("Unsupported OS");
+    }
+}
+
+/// Human readable size, like "312.4 MB".
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Short human readable duration, like "1h 05m" or "42s".
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds >= 3600 {
+        format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60)
+    } else if seconds >= 60 {
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
 pub fn get_file_stem(filename: &str) -> &str {
     if filename.contains(".tar.") {
         let f = Path::new(filename).file_stem().unwrap().to_str().unwrap();
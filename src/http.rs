@@ -0,0 +1,147 @@
+use crate::error::{AblavemaError, Result};
+use lazy_static::lazy_static;
+use reqwest::{header::RETRY_AFTER, Client, ClientBuilder, RequestBuilder, Response, StatusCode};
+use std::{
+    collections::HashMap,
+    ops::{Deref, DerefMut},
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// How long connecting to a server may take before giving up on it.
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a download may go without receiving anything before it counts as stalled.
+pub const READ_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long fetching a whole page may take, which are small enough to not need more.
+const PAGE_TIMEOUT: Duration = Duration::from_secs(60);
+/// Requests that can be in flight at once. Going over this is what got users temporarily banned
+/// when the archives of builder.blender.org were checked all at once.
+const MAX_CONCURRENT_REQUESTS: usize = 4;
+/// Backoff after the first time a server rate limits us, which doubles every time after that.
+const BASE_BACKOFF: Duration = Duration::from_secs(60);
+const MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+/// Hosts known to answer with "403 Forbidden" instead of "429 Too Many Requests" while they
+/// have temporarily banned us.
+const BAN_PRONE_HOSTS: [&str; 1] = ["builder.blender.org"];
+
+lazy_static! {
+    static ref CLIENT: Client = ClientBuilder::new()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .user_agent(concat!(
+            env!("CARGO_PKG_NAME"),
+            "/",
+            env!("CARGO_PKG_VERSION")
+        ))
+        .build()
+        .unwrap();
+    static ref PERMITS: Semaphore = Semaphore::new(MAX_CONCURRENT_REQUESTS);
+    static ref BACKOFFS: Mutex<HashMap<String, Backoff>> = Mutex::new(HashMap::new());
+}
+
+/// How many times in a row a host rate limited us and until when it's left alone.
+#[derive(Clone, Copy, Debug)]
+struct Backoff {
+    strikes: u32,
+    until: SystemTime,
+}
+
+/// The client every request should be built with, so they all share its connection pool.
+pub fn client() -> &'static Client {
+    &CLIENT
+}
+
+/// A response that keeps the slot of its request until it's dropped, since reading the body,
+/// like that of a whole archive, is most of the request.
+pub struct PermittedResponse {
+    response: Response,
+    _permit: SemaphorePermit<'static>,
+}
+
+impl Deref for PermittedResponse {
+    type Target = Response;
+
+    fn deref(&self) -> &Self::Target {
+        &self.response
+    }
+}
+
+impl DerefMut for PermittedResponse {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.response
+    }
+}
+
+/// Fetch the body of a small resource like a page or a checksum file.
+pub async fn get_bytes(url: &str) -> Result<Vec<u8>> {
+    let PermittedResponse { response, _permit } =
+        send(client().get(url).timeout(PAGE_TIMEOUT)).await?;
+    Ok(response.error_for_status()?.bytes().await?.to_vec())
+}
+
+/// Send a request once there's a free slot for it, failing right away if its host is still
+/// being backed off from. Rate limiting responses are turned into errors and start a backoff.
+pub async fn send(request: RequestBuilder) -> Result<PermittedResponse> {
+    let request = request.build()?;
+    let host = request.url().host_str().unwrap_or_default().to_string();
+
+    if let Some(backoff) = backoff(&host) {
+        return Err(AblavemaError::RateLimited {
+            host,
+            until: backoff.until,
+        });
+    }
+
+    let permit = PERMITS.acquire().await.unwrap();
+    let response = CLIENT.execute(request).await?;
+
+    if is_rate_limited(&host, &response) {
+        let until = back_off(&host, retry_after(&response));
+        eprintln!("Rate limited by {}, backing off.", host);
+        return Err(AblavemaError::RateLimited { host, until });
+    }
+
+    BACKOFFS.lock().unwrap().remove(&host);
+    Ok(PermittedResponse {
+        response,
+        _permit: permit,
+    })
+}
+
+/// The backoff of a host, if it isn't over yet.
+fn backoff(host: &str) -> Option<Backoff> {
+    BACKOFFS
+        .lock()
+        .unwrap()
+        .get(host)
+        .filter(|backoff| backoff.until > SystemTime::now())
+        .copied()
+}
+
+/// Start or extend the backoff of a host, returning when it ends.
+fn back_off(host: &str, retry_after: Option<Duration>) -> SystemTime {
+    let mut backoffs = BACKOFFS.lock().unwrap();
+    let strikes = backoffs.get(host).map_or(0, |backoff| backoff.strikes) + 1;
+    let delay = BASE_BACKOFF
+        .saturating_mul(2u32.saturating_pow(strikes - 1))
+        .min(MAX_BACKOFF)
+        .max(retry_after.unwrap_or_default());
+    let until = SystemTime::now() + delay;
+    backoffs.insert(host.to_string(), Backoff { strikes, until });
+    until
+}
+
+fn is_rate_limited(host: &str, response: &Response) -> bool {
+    match response.status() {
+        StatusCode::TOO_MANY_REQUESTS => true,
+        StatusCode::SERVICE_UNAVAILABLE => response.headers().contains_key(RETRY_AFTER),
+        StatusCode::FORBIDDEN => BAN_PRONE_HOSTS.contains(&host),
+        _ => false,
+    }
+}
+
+/// The delay a server asked for, which is only understood when given in seconds.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let seconds = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    seconds.trim().parse().ok().map(Duration::from_secs)
+}
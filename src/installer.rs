@@ -0,0 +1,1059 @@
+use crate::{
+    error::AblavemaError,
+    helpers::{format_bytes, format_duration, send_or_return},
+    http::{self, PermittedResponse},
+    mirrors,
+    package::Package,
+    settings::get_setting,
+};
+use bzip2::read::BzDecoder;
+use flate2::read::GzDecoder;
+use reqwest::{
+    header::{CONTENT_RANGE, RANGE},
+    StatusCode,
+};
+use ron::ser::{to_string_pretty, PrettyConfig};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fmt,
+    fs::{create_dir_all, read_dir, rename, File},
+    io::{self, copy, Read, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use tar::Archive;
+use tokio::{
+    fs::{remove_dir_all, remove_file},
+    io::AsyncWriteExt,
+    sync::mpsc::UnboundedSender,
+    task::spawn_blocking,
+    time::timeout,
+};
+use xz2::read::XzDecoder;
+use zip::ZipArchive;
+
+macro_rules! unwrap_or_return {
+    ($sender:expr, $result:expr) => {
+        match $result {
+            Ok(x) => x,
+            Err(e) => {
+                // Nobody listening means it was cancelled, which is what caused the error.
+                if !$sender.is_closed() {
+                    eprintln!(
+                        "Install error at {}:{}, which was:\n{:#?}",
+                        file!(),
+                        line!(),
+                        e
+                    );
+                    let _ = $sender.send(Progress::Errored(e.to_string()));
+                }
+                return None;
+            }
+        }
+    };
+}
+
+#[derive(Clone, Debug)]
+pub enum Progress {
+    Started,
+    DownloadProgress(DownloadStatus),
+    FinishedDownloading,
+    /// Bytes of the archive that were extracted so far, out of its whole size.
+    ExtractionProgress {
+        extracted: u64,
+        total: u64,
+    },
+    FinishedExtracting,
+    FinishedInstalling,
+    Errored(String),
+}
+
+/// How far along a download is, reported with every chunk that's written.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DownloadStatus {
+    pub downloaded: u64,
+    pub total: u64,
+    /// Bytes per second, which is unknown until the download has run for a moment.
+    pub rate: Option<f64>,
+}
+
+impl DownloadStatus {
+    pub fn percentage(&self) -> f32 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.downloaded as f32 / self.total as f32 * 100.0
+        }
+    }
+
+    /// Time left at the current rate.
+    pub fn eta(&self) -> Option<Duration> {
+        let rate = self.rate.filter(|rate| *rate > 0.0)?;
+        let remaining = self.total.saturating_sub(self.downloaded);
+        Some(Duration::from_secs_f64(remaining as f64 / rate))
+    }
+}
+
+impl fmt::Display for DownloadStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {}",
+            format_bytes(self.downloaded),
+            format_bytes(self.total)
+        )?;
+        if let (Some(rate), Some(eta)) = (self.rate, self.eta()) {
+            write!(
+                f,
+                " at {}/s, {} left",
+                format_bytes(rate as u64),
+                format_duration(eta)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// How often the transfer rate is measured.
+const RATE_SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Weight of the newest sample in the transfer rate, the rest being the previous rate. Keeps
+/// the rate and ETA from jumping around with every hiccup of the connection.
+const RATE_SMOOTHING: f64 = 0.3;
+
+/// Exponential moving average of how many bytes per second are being downloaded.
+struct TransferRate {
+    sample_start: Instant,
+    sample_bytes: u64,
+    rate: Option<f64>,
+}
+
+impl TransferRate {
+    fn new(now: Instant) -> Self {
+        TransferRate {
+            sample_start: now,
+            sample_bytes: 0,
+            rate: None,
+        }
+    }
+
+    fn add(&mut self, bytes: u64, now: Instant) -> Option<f64> {
+        self.sample_bytes += bytes;
+        let elapsed = now.duration_since(self.sample_start);
+
+        if elapsed >= RATE_SAMPLE_INTERVAL {
+            let sample = self.sample_bytes as f64 / elapsed.as_secs_f64();
+            self.rate = Some(match self.rate {
+                Some(rate) => RATE_SMOOTHING * sample + (1.0 - RATE_SMOOTHING) * rate,
+                None => sample,
+            });
+            self.sample_start = now;
+            self.sample_bytes = 0;
+        }
+
+        self.rate
+    }
+}
+
+/// How often extraction progress is reported, in bytes of the archive.
+const EXTRACTION_REPORT_INTERVAL: u64 = 1024 * 1024;
+
+/// Formats that can be extracted on every OS, told apart by the archive's extension.
+enum DownloadedArchive {
+    TarXz,
+    TarBz,
+    TarGz,
+    Zip,
+}
+
+impl DownloadedArchive {
+    fn of(file: &Path) -> Result<Self, AblavemaError> {
+        match file.extension().unwrap_or_default().to_str() {
+            Some("xz") => Ok(DownloadedArchive::TarXz),
+            Some("bz2") => Ok(DownloadedArchive::TarBz),
+            Some("gz") => Ok(DownloadedArchive::TarGz),
+            Some("zip") => Ok(DownloadedArchive::Zip),
+            Some("dmg") => Err(AblavemaError::io(
+                file,
+                "macOS disk images can't be extracted, open it to install the package by hand",
+            )),
+            _ => Err(AblavemaError::io(file, "unknown archive format")),
+        }
+    }
+}
+
+/// Counts the bytes read through it, which is how far along a compressed tarball is, and
+/// reports them as they go so a single huge entry doesn't stall the progress. Reading fails
+/// once the installation is cancelled, so such an entry also stops being unpacked halfway.
+struct CountingReader<R> {
+    inner: R,
+    read: u64,
+    reported: u64,
+    total: u64,
+    sender: UnboundedSender<Progress>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let cancelled = || io::Error::other("the installation was cancelled");
+        if self.sender.is_closed() {
+            return Err(cancelled());
+        }
+
+        let read = self.inner.read(buf)?;
+        self.read += read as u64;
+
+        if self.read - self.reported >= EXTRACTION_REPORT_INTERVAL {
+            self.reported = self.read;
+            self.sender
+                .send(Progress::ExtractionProgress {
+                    extracted: self.read,
+                    total: self.total,
+                })
+                .map_err(|_| cancelled())?;
+        }
+
+        Ok(read)
+    }
+}
+
+/// Where the archive to install comes from.
+#[derive(Clone, Copy, Debug, Deserialize, Hash, PartialEq, Serialize)]
+pub enum Source {
+    Download,
+    /// Reuse the archive left in `cache_dir` by a previous download, which keeps working
+    /// after the package stops being available online.
+    Cache,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum DownloadState {
+    Queued,
+    /// Kept in the queue without being started, its partial file is resumed once it's queued
+    /// again.
+    Paused,
+    Active,
+}
+
+/// A package waiting in the download queue, which only installs
+/// `Settings::max_concurrent_downloads` packages at a time.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct QueuedDownload {
+    pub package: Package,
+    pub source: Source,
+    pub state: DownloadState,
+}
+
+/// Download, extract and register a package, reporting every step through `sender`.
+/// Dropping the receiving end cancels the installation at the next step.
+pub async fn install(package: Package, source: Source, sender: UnboundedSender<Progress>) {
+    let _ = try_install(package, source, sender).await;
+}
+
+async fn try_install(
+    package: Package,
+    source: Source,
+    sender: UnboundedSender<Progress>,
+) -> Option<()> {
+    let package_dir = get_setting().packages_dir.join(&package.name);
+
+    if package_dir.exists() {
+        unwrap_or_return!(sender, remove_dir_all(&package_dir).await);
+    }
+
+    let (file, verified) = match source {
+        Source::Download => match intact_archive(&package).await {
+            Some(file) => (file, true),
+            None => (download(&package, &sender).await?, false),
+        },
+        Source::Cache => match package.cached_archive() {
+            Some(file) => (file, false),
+            None => {
+                let _ = sender.send(Progress::Errored(String::from("no cached archive found")));
+                return None;
+            }
+        },
+    };
+    send_or_return!(sender, Progress::FinishedDownloading);
+
+    if !verified {
+        verify(&package, &file, source, &sender).await?;
+    }
+
+    // Extracting takes a while and only does blocking IO, so it gets a thread of its own.
+    let extraction_dir = {
+        let (package, file, extraction_sender) = (package.clone(), file.clone(), sender.clone());
+        let extraction = spawn_blocking(move || extract(&package, &file, &extraction_sender));
+        unwrap_or_return!(sender, extraction.await)?
+    };
+    send_or_return!(sender, Progress::FinishedExtracting);
+
+    finish(&package, &extraction_dir, &sender)?;
+    let _ = sender.send(Progress::FinishedInstalling);
+
+    Some(())
+}
+
+async fn download(package: &Package, sender: &UnboundedSender<Progress>) -> Option<PathBuf> {
+    let cache_dir = get_setting().cache_dir.clone();
+    unwrap_or_return!(
+        sender,
+        create_dir_all(&cache_dir).map_err(|e| AblavemaError::io(&cache_dir, e))
+    );
+
+    let file = match package.archive_path() {
+        Some(file) => file,
+        None => {
+            let _ = sender.send(Progress::Errored(String::from("no URL to download from")));
+            return None;
+        }
+    };
+    let partial_file = file.with_file_name(format!(
+        "{}.part",
+        file.file_name().unwrap().to_string_lossy()
+    ));
+    let partial_info = file.with_file_name(format!(
+        "{}.part.ron",
+        file.file_name().unwrap().to_string_lossy()
+    ));
+
+    // An archive that's left but doesn't match its checksum isn't worth keeping.
+    if file.exists() {
+        unwrap_or_return!(sender, remove_file(&file).await);
+    }
+
+    // A partial file is only resumed when it belongs to the same URL and hasn't
+    // somehow grown past the length the server reported when it was started.
+    let resumable = PartialDownload::load(&partial_info)
+        .filter(|info| info.url == package.url)
+        .and_then(|info| {
+            let downloaded = std::fs::metadata(&partial_file).ok()?.len();
+            (downloaded > 0 && downloaded < info.total).then_some((downloaded, info.total))
+        });
+
+    let mut connection = None;
+    let mut last_error = String::new();
+
+    // Mirrors all have the same archive, so the next one is tried whenever one can't be reached.
+    for url in package.urls() {
+        match request(&url, resumable).await {
+            Ok(response) => {
+                mirrors::report(&url, true);
+                connection = Some(response);
+                break;
+            }
+            Err(e) => {
+                eprintln!("Couldn't download '{}': {}", url, e);
+                mirrors::report(&url, false);
+                last_error = e;
+            }
+        }
+    }
+
+    let (mut response, mut downloaded, total) = match connection {
+        Some(connection) => connection,
+        None => {
+            let _ = sender.send(Progress::Errored(last_error));
+            return None;
+        }
+    };
+
+    if downloaded == 0 {
+        if partial_file.exists() {
+            unwrap_or_return!(sender, remove_file(&partial_file).await);
+        }
+
+        let info = PartialDownload {
+            url: package.url.clone(),
+            total,
+        };
+        unwrap_or_return!(sender, info.save(&partial_info));
+    }
+
+    let mut destination = unwrap_or_return!(
+        sender,
+        tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&partial_file)
+            .await
+    );
+
+    send_or_return!(sender, Progress::Started);
+    let mut rate = TransferRate::new(Instant::now());
+
+    // A lost connection would otherwise leave the download stuck until it comes back.
+    loop {
+        let chunk = match timeout(http::READ_TIMEOUT, response.chunk()).await {
+            Ok(chunk) => unwrap_or_return!(sender, chunk),
+            Err(_) => {
+                let _ = sender.send(Progress::Errored(format!(
+                    "the download stalled for {} seconds, try again to resume it",
+                    http::READ_TIMEOUT.as_secs()
+                )));
+                return None;
+            }
+        };
+        let chunk = match chunk {
+            Some(chunk) => chunk,
+            None => break,
+        };
+        unwrap_or_return!(sender, destination.write_all(&chunk).await);
+
+        downloaded += chunk.len() as u64;
+        let status = DownloadStatus {
+            downloaded,
+            total,
+            rate: rate.add(chunk.len() as u64, Instant::now()),
+        };
+
+        send_or_return!(sender, Progress::DownloadProgress(status));
+    }
+
+    // Writes on a Tokio file are only guaranteed to have reached it after flushing,
+    // otherwise the extraction may find the archive truncated.
+    unwrap_or_return!(sender, destination.flush().await);
+
+    if downloaded != total {
+        let _ = sender.send(Progress::Errored(format!(
+            "download ended early at {} of {} bytes, try again to resume it",
+            downloaded, total
+        )));
+        return None;
+    }
+
+    unwrap_or_return!(sender, rename(&partial_file, &file));
+    let _ = remove_file(&partial_info).await;
+
+    Some(file)
+}
+
+/// Start downloading from `url`, resuming at the given amount of bytes if possible.
+/// Returns the response along with where it starts and the full length of the archive.
+async fn request(
+    url: &str,
+    resumable: Option<(u64, u64)>,
+) -> Result<(PermittedResponse, u64, u64), String> {
+    if let Some((downloaded, total)) = resumable {
+        let response = http::send(
+            http::client()
+                .get(url)
+                .header(RANGE, format!("bytes={}-", downloaded)),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+        // Servers that don't support ranges answer with the whole file instead,
+        // in which case it's simpler to start over with a fresh request.
+        if response.status() == StatusCode::PARTIAL_CONTENT
+            && content_range_start_and_total(&response) == Some((downloaded, total))
+        {
+            return Ok((response, downloaded, total));
+        }
+
+        println!("Server refused to resume '{}', downloading it again.", url);
+    }
+
+    let response = http::send(http::client().get(url))
+        .await
+        .map_err(|e| e.to_string())?;
+    response.error_for_status_ref().map_err(|e| e.to_string())?;
+
+    match response.content_length() {
+        Some(total) => Ok((response, 0, total)),
+        None => Err(String::from("cannot find content length")),
+    }
+}
+
+/// What's needed to resume an interrupted download, stored next to the partial file.
+#[derive(Debug, Deserialize, Serialize)]
+struct PartialDownload {
+    url: String,
+    total: u64,
+}
+
+impl PartialDownload {
+    fn load(path: &Path) -> Option<Self> {
+        ron::from_str(&std::fs::read_to_string(path).ok()?).ok()
+    }
+
+    fn save(&self, path: &Path) -> std::io::Result<()> {
+        let serialized = to_string_pretty(self, PrettyConfig::default()).unwrap();
+        File::create(path)?.write_all(serialized.as_bytes())
+    }
+}
+
+/// Parse the `Content-Range: bytes start-end/total` header of a partial response.
+fn content_range_start_and_total(response: &reqwest::Response) -> Option<(u64, u64)> {
+    let content_range = response.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    let (range, total) = content_range.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _end) = range.split_once('-')?;
+
+    Some((start.parse().ok()?, total.parse().ok()?))
+}
+
+/// Compare the downloaded archive against its published SHA-256 checksum, if there's one.
+/// A mismatching archive is removed so it can't be extracted or reused.
+async fn verify(
+    package: &Package,
+    file: &Path,
+    source: Source,
+    sender: &UnboundedSender<Progress>,
+) -> Option<()> {
+    if package.checksum_url.is_empty() {
+        return Some(());
+    }
+
+    let checksums = match fetch_checksums(&mirrors::resolve(&package.checksum_url)).await {
+        // Cached archives are mostly reinstalled once their package is gone, and the
+        // checksum with it, which shouldn't make the archive itself unusable.
+        Err(error) if source == Source::Cache => {
+            eprintln!(
+                "Couldn't fetch the checksum for '{}', skipping verification: {}",
+                package.name, error
+            );
+            return Some(());
+        }
+        result => unwrap_or_return!(sender, result),
+    };
+    let file_name = file.file_name().unwrap().to_str().unwrap();
+
+    let expected = match parse_checksum(&checksums, file_name) {
+        Some(expected) => expected,
+        None => {
+            eprintln!("No published checksum found for '{}'.", file_name);
+            return Some(());
+        }
+    };
+
+    let path = file.to_path_buf();
+    let actual = unwrap_or_return!(
+        sender,
+        unwrap_or_return!(sender, spawn_blocking(move || hash_file(&path)).await)
+    );
+
+    if actual != expected {
+        let _ = remove_file(file).await;
+        let _ = sender.send(Progress::Errored(format!(
+            "checksum mismatch for '{}'",
+            file_name
+        )));
+        return None;
+    }
+
+    Some(())
+}
+
+/// The archive left by a download that finished without being installed, like when it was
+/// paused or cancelled while being verified or extracted, as long as it matches its published
+/// checksum. Without a checksum to compare against, it's downloaded again.
+async fn intact_archive(package: &Package) -> Option<PathBuf> {
+    let file = package.archive_path()?;
+    if package.checksum_url.is_empty() || !file.exists() {
+        return None;
+    }
+
+    let checksums = fetch_checksums(&mirrors::resolve(&package.checksum_url))
+        .await
+        .ok()?;
+    let expected = parse_checksum(&checksums, file.file_name()?.to_str()?)?;
+
+    let path = file.clone();
+    let actual = spawn_blocking(move || hash_file(&path)).await.ok()?.ok()?;
+
+    (actual == expected).then_some(file)
+}
+
+/// Fetch the first checksum file that can be reached out of the given mirrors.
+async fn fetch_checksums(urls: &[String]) -> Result<String, AblavemaError> {
+    let mut result = None;
+
+    for url in urls {
+        let response = http::get_bytes(url)
+            .await
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned());
+        mirrors::report(url, response.is_ok());
+
+        if response.is_ok() {
+            return response;
+        }
+        result = Some(response);
+    }
+
+    result.unwrap()
+}
+
+/// Checksum files either contain a lone hash or lines in the `sha256sum` format.
+fn parse_checksum(checksums: &str, file_name: &str) -> Option<String> {
+    for line in checksums.lines() {
+        let mut words = line.split_whitespace();
+
+        match (words.next(), words.next()) {
+            (Some(hash), None) if hash.len() == 64 => return Some(hash.to_lowercase()),
+            (Some(hash), Some(name)) if name.trim_start_matches('*') == file_name => {
+                return Some(hash.to_lowercase())
+            }
+            _ => (),
+        }
+    }
+
+    None
+}
+
+fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Returns the directory that ended up containing the package's files.
+fn extract(package: &Package, file: &Path, sender: &UnboundedSender<Progress>) -> Option<PathBuf> {
+    extract_into(file, &get_setting().cache_dir.join(&package.name), sender)
+}
+
+/// Whatever was extracted is removed when the extraction doesn't finish, like when it's
+/// cancelled halfway.
+fn extract_into(
+    file: &Path,
+    extraction_dir: &Path,
+    sender: &UnboundedSender<Progress>,
+) -> Option<PathBuf> {
+    let extracted = unpack(file, extraction_dir, sender);
+    if extracted.is_none() {
+        let _ = std::fs::remove_dir_all(extraction_dir);
+    }
+    extracted.map(|()| extraction_dir.to_path_buf())
+}
+
+fn unpack(file: &Path, extraction_dir: &Path, sender: &UnboundedSender<Progress>) -> Option<()> {
+    let archive = unwrap_or_return!(sender, DownloadedArchive::of(file));
+
+    // Leftovers of an extraction that didn't finish would end up mixed into the package.
+    if extraction_dir.exists() {
+        unwrap_or_return!(
+            sender,
+            std::fs::remove_dir_all(extraction_dir)
+                .map_err(|e| AblavemaError::io(extraction_dir, e))
+        );
+    }
+    unwrap_or_return!(
+        sender,
+        create_dir_all(extraction_dir).map_err(|e| AblavemaError::io(extraction_dir, e))
+    );
+
+    let total = unwrap_or_return!(
+        sender,
+        file.metadata()
+            .map(|metadata| metadata.len())
+            .map_err(|e| AblavemaError::io(file, e))
+    );
+    send_or_return!(
+        sender,
+        Progress::ExtractionProgress {
+            extracted: 0,
+            total
+        }
+    );
+
+    match archive {
+        DownloadedArchive::TarXz => {
+            extract_tar(file, XzDecoder::new, total, extraction_dir, sender)?
+        }
+        DownloadedArchive::TarBz => {
+            extract_tar(file, BzDecoder::new, total, extraction_dir, sender)?
+        }
+        DownloadedArchive::TarGz => {
+            extract_tar(file, GzDecoder::new, total, extraction_dir, sender)?
+        }
+        DownloadedArchive::Zip => extract_zip(file, total, extraction_dir, sender)?,
+    }
+
+    send_or_return!(
+        sender,
+        Progress::ExtractionProgress {
+            extracted: total,
+            total
+        }
+    );
+
+    Some(())
+}
+
+/// Unpack a tarball compressed with whatever `decoder` undoes.
+fn extract_tar<D: Read>(
+    file: &Path,
+    decoder: impl FnOnce(CountingReader<File>) -> D,
+    total: u64,
+    extraction_dir: &Path,
+    sender: &UnboundedSender<Progress>,
+) -> Option<()> {
+    let reader = CountingReader {
+        inner: unwrap_or_return!(sender, File::open(file)),
+        read: 0,
+        reported: 0,
+        total,
+        sender: sender.clone(),
+    };
+    let mut archive = Archive::new(decoder(reader));
+
+    for entry in unwrap_or_return!(sender, archive.entries()) {
+        let mut entry = unwrap_or_return!(sender, entry);
+        unwrap_or_return!(sender, entry.unpack_in(extraction_dir));
+    }
+
+    Some(())
+}
+
+/// Unpack a zip archive, where progress goes by the compressed size of each entry since
+/// reading one means jumping around the file.
+fn extract_zip(
+    file: &Path,
+    total: u64,
+    extraction_dir: &Path,
+    sender: &UnboundedSender<Progress>,
+) -> Option<()> {
+    let mut archive = unwrap_or_return!(
+        sender,
+        ZipArchive::new(unwrap_or_return!(sender, File::open(file)))
+    );
+    let (mut extracted, mut reported) = (0, 0);
+
+    for index in 0..archive.len() {
+        if sender.is_closed() {
+            return None;
+        }
+
+        let mut entry = unwrap_or_return!(sender, archive.by_index(index));
+        extracted += entry.compressed_size();
+
+        // Entries that would end up outside of the extraction directory are skipped.
+        let path = match entry.enclosed_name() {
+            Some(path) => extraction_dir.join(path),
+            None => continue,
+        };
+
+        if entry.is_dir() {
+            unwrap_or_return!(sender, create_dir_all(&path));
+        } else {
+            if let Some(parent) = path.parent() {
+                unwrap_or_return!(sender, create_dir_all(parent));
+            }
+            let mut extracted_file = unwrap_or_return!(sender, File::create(&path));
+            unwrap_or_return!(sender, copy(&mut entry, &mut extracted_file));
+
+            #[cfg(unix)]
+            if let Some(mode) = entry.unix_mode() {
+                use std::{fs::set_permissions, os::unix::fs::PermissionsExt};
+                unwrap_or_return!(
+                    sender,
+                    set_permissions(&path, std::fs::Permissions::from_mode(mode))
+                );
+            }
+        }
+
+        if extracted - reported >= EXTRACTION_REPORT_INTERVAL {
+            reported = extracted;
+            send_or_return!(
+                sender,
+                Progress::ExtractionProgress {
+                    extracted: reported,
+                    total
+                }
+            );
+        }
+    }
+
+    Some(())
+}
+
+/// Where the package's files ended up in the extraction directory. Archives normally hold a
+/// single directory with the package in it, but some old ones have its files right at the root.
+fn extracted_root(extraction_dir: &Path) -> Result<PathBuf, AblavemaError> {
+    let entries: Vec<PathBuf> = read_dir(extraction_dir)
+        .and_then(|entries| entries.map(|entry| Ok(entry?.path())).collect())
+        .map_err(|e| AblavemaError::io(extraction_dir, e))?;
+
+    match entries.as_slice() {
+        [] => Err(AblavemaError::io(extraction_dir, "the archive was empty")),
+        [directory] if directory.is_dir() => Ok(directory.clone()),
+        _ => Ok(extraction_dir.to_path_buf()),
+    }
+}
+
+/// Move the extracted package into the packages directory and write its `package_info.ron`.
+fn finish(
+    package: &Package,
+    extraction_dir: &Path,
+    sender: &UnboundedSender<Progress>,
+) -> Option<()> {
+    // The extraction may have outlived a cancellation, so don't install in that case.
+    if sender.is_closed() {
+        return None;
+    }
+
+    let extracted_path = unwrap_or_return!(sender, extracted_root(extraction_dir));
+
+    let mut package_path = get_setting().packages_dir.join(&package.name);
+
+    // TODO: Fix moving directories across filesystems.
+    // Can probably use the `fs_extra` crate, which I'm already depending on.
+    // Actually, I tried it but that crate fails to move links.
+    unwrap_or_return!(sender, rename(extracted_path, &package_path));
+
+    package_path.push("package_info.ron");
+    let mut file = unwrap_or_return!(sender, File::create(&package_path));
+    let package_info = unwrap_or_return!(sender, to_string_pretty(package, PrettyConfig::new()));
+    unwrap_or_return!(sender, file.write_all(package_info.as_bytes()));
+
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::scratch_dir;
+    use flate2::{write::GzEncoder, Compression};
+    use std::{fs::remove_dir_all, thread};
+    use tokio::sync::mpsc::unbounded_channel;
+    use zip::{write::FileOptions, ZipWriter};
+
+    /// An empty directory to build and extract archives in, removed when done.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = scratch_dir(name);
+        create_dir_all(dir.join("extracted")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn extract_zip_anywhere() {
+        let dir = scratch("zip");
+        let archive = dir.join("blender-4.1.1-windows-x64.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        zip.add_directory("blender-4.1.1-windows-x64/", FileOptions::default())
+            .unwrap();
+        zip.start_file(
+            "blender-4.1.1-windows-x64/blender.exe",
+            FileOptions::default().unix_permissions(0o755),
+        )
+        .unwrap();
+        zip.write_all(b"not really blender").unwrap();
+        zip.start_file("../outside.txt", FileOptions::default())
+            .unwrap();
+        zip.write_all(b"escaped").unwrap();
+        zip.finish().unwrap();
+
+        let (sender, _receiver) = unbounded_channel();
+        let extraction_dir = dir.join("extracted");
+        let total = archive.metadata().unwrap().len();
+        extract_zip(&archive, total, &extraction_dir, &sender).unwrap();
+
+        let root = extracted_root(&extraction_dir).unwrap();
+        assert_eq!(root, extraction_dir.join("blender-4.1.1-windows-x64"));
+        let executable = root.join("blender.exe");
+        assert_eq!(std::fs::read(&executable).unwrap(), b"not really blender");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = executable.metadata().unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o755);
+        }
+        assert!(!dir.join("outside.txt").exists());
+
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn extract_tar_gz_without_inner_directory() {
+        let dir = scratch("tar-gz");
+        let archive = dir.join("blender-2.49-linux.tar.gz");
+        let mut tar = tar::Builder::new(GzEncoder::new(
+            File::create(&archive).unwrap(),
+            Compression::default(),
+        ));
+        for name in ["blender", "readme.txt"] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(4);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, name, &b"data"[..]).unwrap();
+        }
+        tar.into_inner().unwrap().finish().unwrap();
+
+        let (sender, _receiver) = unbounded_channel();
+        let extraction_dir = dir.join("extracted");
+        let total = archive.metadata().unwrap().len();
+        extract_tar(&archive, GzDecoder::new, total, &extraction_dir, &sender).unwrap();
+
+        assert_eq!(extracted_root(&extraction_dir).unwrap(), extraction_dir);
+        assert_eq!(
+            std::fs::read(extraction_dir.join("readme.txt")).unwrap(),
+            b"data"
+        );
+
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn cancel_tar_extraction() {
+        let dir = scratch("tar-cancel");
+        let archive = dir.join("blender-4.1.1-linux-x64.tar.gz");
+        let mut tar = tar::Builder::new(GzEncoder::new(
+            File::create(&archive).unwrap(),
+            Compression::default(),
+        ));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, "blender", &b"data"[..])
+            .unwrap();
+        tar.into_inner().unwrap().finish().unwrap();
+
+        let (sender, receiver) = unbounded_channel();
+        drop(receiver);
+        let extraction_dir = dir.join("extracted");
+        let total = archive.metadata().unwrap().len();
+        assert!(extract_tar(&archive, GzDecoder::new, total, &extraction_dir, &sender).is_none());
+        assert!(!extraction_dir.join("blender").exists());
+
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn cancel_tar_extraction_halfway() {
+        let dir = scratch("tar-cancel-halfway");
+        let archive = dir.join("blender-4.1.1-linux-x64.tar.gz");
+        let mut tar = tar::Builder::new(GzEncoder::new(
+            File::create(&archive).unwrap(),
+            Compression::none(),
+        ));
+        // Several times the reporting interval, so there's plenty left once it's cancelled.
+        let mut seed: u32 = 1;
+        let data: Vec<u8> = (0..EXTRACTION_REPORT_INTERVAL)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (seed >> 24) as u8
+            })
+            .collect();
+        for index in 0..24 {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, format!("blender/{}.bin", index), &data[..])
+                .unwrap();
+        }
+        tar.into_inner().unwrap().finish().unwrap();
+
+        let (sender, mut receiver) = unbounded_channel();
+        let extraction_dir = dir.join("extracted");
+        let extraction = {
+            let (archive, extraction_dir) = (archive.clone(), extraction_dir.clone());
+            thread::spawn(move || extract_into(&archive, &extraction_dir, &sender))
+        };
+
+        let total = archive.metadata().unwrap().len();
+        loop {
+            match receiver.blocking_recv() {
+                Some(Progress::ExtractionProgress { extracted, .. }) if extracted > 0 => {
+                    assert!(extracted < total);
+                    break;
+                }
+                Some(_) => (),
+                None => panic!("the extraction ended without reporting progress"),
+            }
+        }
+        drop(receiver);
+
+        assert!(extraction.join().unwrap().is_none());
+        assert!(!extraction_dir.exists());
+
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn report_progress_within_an_entry() {
+        let dir = scratch("tar-progress");
+        let archive = dir.join("blender-4.1.1-linux-x64.tar.gz");
+        let mut tar = tar::Builder::new(GzEncoder::new(
+            File::create(&archive).unwrap(),
+            Compression::none(),
+        ));
+        let data = vec![0; 4 * EXTRACTION_REPORT_INTERVAL as usize];
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        tar.append_data(&mut header, "blender/blender", &data[..])
+            .unwrap();
+        tar.into_inner().unwrap().finish().unwrap();
+
+        let (sender, mut receiver) = unbounded_channel();
+        let extraction_dir = dir.join("extracted");
+        let total = archive.metadata().unwrap().len();
+        extract_tar(&archive, GzDecoder::new, total, &extraction_dir, &sender).unwrap();
+        drop(sender);
+
+        let mut reports = 0;
+        while let Some(Progress::ExtractionProgress { extracted, .. }) = receiver.blocking_recv() {
+            assert!(extracted < total);
+            reports += 1;
+        }
+        assert!(reports >= 3, "{}", reports);
+
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn archive_formats() {
+        for name in ["a.tar.xz", "a.tar.bz2", "a.tar.gz", "a.zip"] {
+            assert!(DownloadedArchive::of(Path::new(name)).is_ok(), "{}", name);
+        }
+        let error = DownloadedArchive::of(Path::new("blender-4.1.1-macos-arm64.dmg"))
+            .err()
+            .unwrap();
+        assert!(
+            error.to_string().contains("can't be extracted"),
+            "{}",
+            error
+        );
+        assert!(DownloadedArchive::of(Path::new("blender.msi")).is_err());
+    }
+
+    #[test]
+    fn smoothed_rate_and_eta() {
+        let start = Instant::now();
+        let mut rate = TransferRate::new(start);
+
+        // Nothing's known until a whole sample was taken.
+        assert_eq!(rate.add(1000, start + Duration::from_millis(100)), None);
+        assert_eq!(
+            rate.add(0, start + Duration::from_millis(500)),
+            Some(2000.0)
+        );
+
+        // A sudden burst only moves the rate part of the way.
+        let burst = rate.add(6000, start + Duration::from_millis(1500)).unwrap();
+        assert!((burst - 3200.0).abs() < 0.001, "{}", burst);
+
+        let status = DownloadStatus {
+            downloaded: 1024,
+            total: 1024 * 1024 + 1024,
+            rate: Some(1024.0 * 10.0),
+        };
+        assert_eq!(status.eta(), Some(Duration::from_secs_f64(102.4)));
+        assert_eq!(
+            status.to_string(),
+            "1.0 KB of 1.0 MB at 10.0 KB/s, 1m 42s left"
+        );
+
+        let status = DownloadStatus {
+            rate: None,
+            ..status
+        };
+        assert_eq!(status.eta(), None);
+        assert_eq!(status.to_string(), "1.0 KB of 1.0 MB");
+    }
+}
@@ -1,23 +1,39 @@
 #![windows_subsystem = "windows"]
 #![warn(rust_2018_idioms)]
 //#![allow(dead_code, unused_imports, unused_variables)]
+mod association;
+mod blend_file;
 mod cli;
+mod commit_log;
+mod error;
 mod gui;
 mod helpers;
+mod http;
+mod installer;
+mod mirrors;
 mod package;
+mod profiles;
+mod query;
 mod releases;
+mod renderer;
 mod self_updater;
 mod settings;
+#[cfg(test)]
+mod test_helpers;
+mod version_pin;
 use crate::{
+    association::matching_rule,
+    blend_file::{closest_package, saved_with},
     cli::run_cli,
     gui::Gui,
-    helpers::open_blender,
+    helpers::{confirm_opening, open_blender},
     settings::{get_setting, LAUNCH_GUI},
+    version_pin::VersionPin,
 };
 use helpers::check_connection;
 use iced::pure::Application;
 use settings::TEXT_SIZE;
-use std::{env, sync::atomic::Ordering};
+use std::{env, path::Path, sync::atomic::Ordering};
 
 // TODO: Fix window cascading on Windows. This will involve creating our own window which we'll
 // give to Iced.
@@ -47,7 +63,24 @@ async fn main() {
 async fn run() {
     let gui_args = run_cli().await;
 
-    if LAUNCH_GUI.load(Ordering::Relaxed) || get_setting().default_package.is_none() {
+    // A pinned package that isn't installed is offered for installing in the GUI.
+    let pinned = gui_args.file_path.as_ref().and_then(|file_path| {
+        let pin = VersionPin::find(Path::new(file_path))?;
+        let installed = pin.installed(gui_args.releases.installed.iter());
+        if installed.is_none() {
+            println!(
+                "'{}' pins '{}', which isn't installed.",
+                pin.file.display(),
+                pin.query
+            );
+        }
+        Some(installed.map(|package| package.name.clone()))
+    });
+
+    if LAUNCH_GUI.load(Ordering::Relaxed)
+        || get_setting().default_package.is_none()
+        || matches!(pinned, Some(None))
+    {
         let mut window = iced::window::Settings::default();
         window.size = (680, 620);
         window.min_size = Some((680, 620));
@@ -69,10 +102,48 @@ async fn run() {
         Gui::run(settings).unwrap();
     } else {
         match &gui_args.file_path {
-            Some(file_path) => open_blender(
-                get_setting().default_package.clone().unwrap().name,
-                Some(file_path.to_owned()),
-            ),
+            Some(file_path) => {
+                let associated =
+                    matching_rule(&get_setting().association_rules, Path::new(file_path)).and_then(
+                        |(_, rule)| {
+                            let installed = rule.installed(gui_args.releases.installed.iter());
+                            if installed.is_none() {
+                                println!(
+                                    "The rule for '{}' picks '{}', which isn't installed.",
+                                    rule.pattern, rule.query
+                                );
+                            }
+                            installed.map(|package| package.name.clone())
+                        },
+                    );
+
+                let closest = if get_setting().open_with_closest_package {
+                    saved_with(Path::new(file_path)).ok().and_then(|version| {
+                        closest_package(version, gui_args.releases.installed.iter())
+                            .map(|package| package.name.clone())
+                    })
+                } else {
+                    None
+                };
+
+                let package = pinned
+                    .flatten()
+                    .or(associated)
+                    .or(closest)
+                    .unwrap_or_else(|| get_setting().default_package.clone().unwrap().name);
+                if let Some(installed) = gui_args
+                    .releases
+                    .installed
+                    .iter()
+                    .find(|installed| installed.name == package)
+                {
+                    if !confirm_opening(Path::new(file_path), installed) {
+                        return;
+                    }
+                }
+
+                open_blender(package, Some(file_path.to_owned()))
+            }
             None => open_blender(get_setting().default_package.clone().unwrap().name, None),
         }
     }
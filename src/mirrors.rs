@@ -0,0 +1,152 @@
+use crate::{
+    error::{AblavemaError, Result},
+    settings::{get_setting, save_settings, set_setting},
+};
+use select::document::Document;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+
+/// Mirrors of Blender's release directory that are used when none are configured.
+/// Package URLs that start with any of them are also treated as relative to the mirror root,
+/// which is how databases from before mirrors were configurable keep working.
+pub const DEFAULT_MIRRORS: [&str; 2] = [
+    "https://ftp.nluug.nl/pub/graphics/blender/release/",
+    "https://download.blender.org/release/",
+];
+
+/// How long failures count against a mirror before it's given another chance.
+const FAILURE_EXPIRY: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// A copy of `https://download.blender.org/release/`, where stable and LTS packages come from.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Mirror {
+    /// Root of the mirror, ending with a slash.
+    pub url: String,
+    /// Failed requests in a row, which gets reset by a successful one.
+    #[serde(default)]
+    pub failures: u32,
+    #[serde(default)]
+    pub last_failure: Option<SystemTime>,
+}
+
+impl Mirror {
+    pub fn new(url: &str) -> Self {
+        let mut url = url.trim().to_string();
+        if !url.ends_with('/') {
+            url.push('/');
+        }
+
+        Mirror {
+            url,
+            failures: 0,
+            last_failure: None,
+        }
+    }
+
+    pub fn defaults() -> Vec<Mirror> {
+        DEFAULT_MIRRORS.iter().map(|url| Mirror::new(url)).collect()
+    }
+
+    /// Failures that still count, which is none once the last one is old enough.
+    pub fn recent_failures(&self) -> u32 {
+        match self.last_failure.map(|time| time.elapsed()) {
+            Some(Ok(elapsed)) if elapsed < FAILURE_EXPIRY => self.failures,
+            _ => 0,
+        }
+    }
+}
+
+/// Roots of the configured mirrors, the healthiest first and otherwise in the order they were
+/// configured in.
+pub fn ranked() -> Vec<String> {
+    let mut mirrors = get_setting().mirrors.clone();
+    if mirrors.is_empty() {
+        mirrors = Mirror::defaults();
+    }
+    mirrors.sort_by_key(Mirror::recent_failures);
+    mirrors.into_iter().map(|mirror| mirror.url).collect()
+}
+
+/// Path of a package URL relative to the mirror root, if it belongs to a mirror at all.
+pub fn relative(url: &str) -> Option<&str> {
+    if !url.contains("://") {
+        return Some(url);
+    }
+
+    let configured = get_setting()
+        .mirrors
+        .iter()
+        .find(|mirror| url.starts_with(&mirror.url))
+        .map(|mirror| mirror.url.len());
+
+    configured
+        .or_else(|| {
+            DEFAULT_MIRRORS
+                .iter()
+                .find(|mirror| url.starts_with(**mirror))
+                .map(|mirror| mirror.len())
+        })
+        .map(|root_length| &url[root_length..])
+}
+
+/// Every URL a stored package URL can be downloaded from, in the order they should be tried.
+/// URLs that don't belong to a mirror, like those of daily builds, are returned as they are.
+pub fn resolve(url: &str) -> Vec<String> {
+    match relative(url) {
+        Some(path) => ranked()
+            .into_iter()
+            .map(|root| format!("{}{}", root, path))
+            .collect(),
+        None => vec![url.to_string()],
+    }
+}
+
+/// Keep track of whether a request to a mirror went through, so failing mirrors are tried last.
+pub fn report(url: &str, succeeded: bool) {
+    let mut settings = set_setting();
+    let mirror = match settings
+        .mirrors
+        .iter_mut()
+        .find(|mirror| url.starts_with(&mirror.url))
+    {
+        Some(mirror) => mirror,
+        None => return,
+    };
+
+    if succeeded && mirror.failures == 0 {
+        return;
+    } else if succeeded {
+        mirror.failures = 0;
+        mirror.last_failure = None;
+    } else {
+        mirror.failures = mirror.recent_failures() + 1;
+        mirror.last_failure = Some(SystemTime::now());
+    }
+    drop(settings);
+    save_settings();
+}
+
+/// Fetch a page of the release directory, moving on to the next mirror whenever one fails.
+/// The error of the last mirror is returned if none of them work.
+pub async fn get_document(path: &str) -> Result<Document> {
+    let mut last_error = None;
+
+    for url in resolve(path) {
+        match crate::helpers::get_document(&url).await {
+            Ok(document) => {
+                report(&url, true);
+                return Ok(document);
+            }
+            Err(e) => {
+                eprintln!("Mirror failed, trying the next one: {}", e);
+                report(&url, false);
+                last_error = Some(e);
+            }
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| AblavemaError::Network {
+        url: path.to_string(),
+        message: String::from("no mirrors configured"),
+    }))
+}
@@ -1,21 +1,36 @@
-use crate::settings::get_setting;
+use crate::{
+    helpers::get_executable_name,
+    installer::DownloadStatus,
+    mirrors,
+    settings::{get_setting, save_settings, set_setting},
+};
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::{fmt::Write, fs::remove_dir_all, mem};
+use std::{fmt::Write, fs::remove_dir_all, mem, path::PathBuf};
 use timeago::{self, TimeUnit::Minutes};
 use versions::Versioning;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Package {
-    // TODO: Add "label" field so users can describe what a package is for if needed.
     pub version: Versioning,
     pub name: String,
+    /// Lets users describe what a package is for.
+    #[serde(default)]
+    pub label: String,
     pub build: Build,
     pub date: NaiveDateTime,
     pub commit: String,
     pub url: String,
+    /// Published SHA-256 checksums for the archive at `url`, if there are any.
+    #[serde(default)]
+    pub checksum_url: String,
     pub os: Os,
+    #[serde(default)]
+    pub arch: Arch,
     pub changelog: Vec<Change>,
+    /// Directory or executable of a custom package, which lives outside of `packages_dir`.
+    #[serde(default)]
+    pub path: Option<PathBuf>,
     #[serde(skip)]
     pub state: PackageState,
     #[serde(skip)]
@@ -24,6 +39,13 @@ pub struct Package {
     pub index: usize,
     #[serde(skip)]
     pub build_type: BuildType,
+    #[serde(skip)]
+    pub cached: bool,
+    /// Changes of the newer point releases in the same LTS series, paired with their version.
+    #[serde(skip)]
+    pub newer_changes: Vec<(Versioning, Change)>,
+    #[serde(skip)]
+    pub changelog_view: Option<ChangelogView>,
 }
 
 impl Package {
@@ -43,15 +65,122 @@ impl Package {
         )
     }
 
+    /// Register a self-compiled or otherwise locally available Blender.
+    /// `path` can be either the directory containing the executable or the executable itself.
+    pub fn new_custom(
+        name: &str,
+        version: &str,
+        label: &str,
+        path: PathBuf,
+    ) -> Result<Package, String> {
+        let name = name.trim();
+
+        if name.is_empty() {
+            return Err(String::from("the name can't be empty"));
+        }
+
+        let version = match Versioning::new(version.trim()) {
+            Some(version) if version.nth(0).is_some() => version,
+            _ => return Err(format!("'{}' is not a valid version", version.trim())),
+        };
+
+        let path = match path.canonicalize() {
+            Ok(path) => path,
+            Err(_) => return Err(format!("couldn't find '{}'", path.display())),
+        };
+
+        // Anything inside the packages directory is fair game for removal.
+        if path.starts_with(&get_setting().packages_dir) {
+            return Err(String::from(
+                "custom packages can't be inside the packages directory",
+            ));
+        }
+
+        let package = Package {
+            version,
+            name: name.to_string(),
+            label: label.trim().to_string(),
+            build: Build::Custom,
+            date: Utc::now().naive_utc(),
+            path: Some(path),
+            os: Os::current(),
+            arch: Arch::current(),
+            ..Default::default()
+        };
+
+        if !package.executable().is_file() {
+            return Err(format!(
+                "couldn't find an executable at '{}'",
+                package.executable().display()
+            ));
+        }
+
+        Ok(package)
+    }
+
+    /// The Blender executable of this package.
+    pub fn executable(&self) -> PathBuf {
+        match &self.path {
+            Some(path) if path.is_dir() => path.join(get_executable_name()),
+            Some(path) => path.clone(),
+            None => get_setting()
+                .packages_dir
+                .join(&self.name)
+                .join(get_executable_name()),
+        }
+    }
+
+    /// Custom packages are only unregistered, since their files aren't ours to delete.
     pub fn remove(&self) {
+        if self.build == Build::Custom {
+            set_setting()
+                .custom_packages
+                .retain(|package| package.name != self.name);
+            save_settings();
+            println!("Unregistered: {}", self.name);
+            return;
+        }
+
         let path = get_setting().packages_dir.join(&self.name);
         let _ = remove_dir_all(path);
         println!("Removed: {}", self.name);
     }
 
+    /// Every URL the package can be downloaded from, in the order they should be tried.
+    pub fn urls(&self) -> Vec<String> {
+        mirrors::resolve(&self.url)
+    }
+
+    /// Where the archive of this package is kept after being downloaded, if it has one to
+    /// download at all, unlike custom packages.
+    pub fn archive_path(&self) -> Option<PathBuf> {
+        let file_name = self.url.split_terminator('/').last()?;
+        Some(get_setting().cache_dir.join(file_name))
+    }
+
+    /// The downloaded archive of this package, if it's still in the cache.
+    /// Interrupted downloads are kept under a different name, so this is always complete.
+    pub fn cached_archive(&self) -> Option<PathBuf> {
+        let path = self.archive_path()?;
+        path.is_file().then_some(path)
+    }
+
     pub fn take(&mut self) -> Self {
         mem::take(self)
     }
+
+    pub fn target(&self) -> Target {
+        Target {
+            os: self.os,
+            arch: self.arch,
+        }
+    }
+
+    /// Whether this package was built for the machine Ablavema is running on, since packages of
+    /// other targets can be installed but not launched.
+    pub fn runs_here(&self) -> bool {
+        self.target() == Target::current()
+    }
 }
 
 impl Default for Package {
@@ -59,6 +188,7 @@ impl Default for Package {
         Package {
             version: Versioning::default(),
             name: String::default(),
+            label: String::default(),
             build: Build::StableArchive,
             date: NaiveDateTime::new(
                 NaiveDate::from_ymd(1999, 12, 31),
@@ -66,12 +196,18 @@ impl Default for Package {
             ),
             commit: String::default(),
             url: String::default(),
+            checksum_url: String::default(),
             os: Os::Linux,
+            arch: Arch::default(),
             changelog: Vec::default(),
+            path: None,
             state: PackageState::default(),
             status: PackageStatus::default(),
             index: 0,
             build_type: BuildType::None,
+            cached: false,
+            newer_changes: Vec::new(),
+            changelog_view: None,
         }
     }
 }
@@ -90,7 +226,7 @@ impl Ord for Package {
                 .build
                 .cmp(&other.build)
                 .then(self.date.cmp(&other.date).reverse()),
-            Build::StableLatest | Build::StableArchive | Build::Lts => {
+            Build::StableLatest | Build::StableArchive | Build::Lts | Build::Custom => {
                 Ord::cmp(&self.version, &other.version).reverse()
             }
         }
@@ -111,8 +247,12 @@ impl PartialEq for Package {
             | Build::ExperimentalLatest(_)
             | Build::ExperimentalArchive(_)
             | Build::PatchLatest(_)
-            | Build::PatchArchive(_) => self.build == other.build && self.date == other.date,
-            Build::StableLatest | Build::StableArchive | Build::Lts => {
+            | Build::PatchArchive(_) => {
+                self.build == other.build
+                    && self.date == other.date
+                    && self.target() == other.target()
+            }
+            Build::StableLatest | Build::StableArchive | Build::Lts | Build::Custom => {
                 self.name == other.name && self.version == other.version
             }
         }
@@ -136,28 +276,187 @@ pub enum Build {
     StableLatest,
     StableArchive,
     Lts,
+    Custom,
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum Os {
     Linux,
     Windows,
     MacOs,
 }
 
+impl Os {
+    pub const ALL: [Os; 3] = [Os::Linux, Os::Windows, Os::MacOs];
+
+    pub fn current() -> Self {
+        if cfg!(target_os = "linux") {
+            Os::Linux
+        } else if cfg!(target_os = "windows") {
+            Os::Windows
+        } else if cfg!(target_os = "macos") {
+            Os::MacOs
+        } else {
+            unreachable!("Unexpected OS");
+        }
+    }
+
+    /// The OS an archive is for, going by its file name, if it's for one of those supported.
+    ///
+    /// The platform comes after the version and, for builder builds, the branch, so the last
+    /// platform in the name is the one that counts.
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        const PLATFORMS: [(&str, Os); 7] = [
+            ("-linux", Os::Linux),
+            ("-windows", Os::Windows),
+            ("-win64", Os::Windows),
+            ("-win32", Os::Windows),
+            ("-macos", Os::MacOs),
+            ("-darwin", Os::MacOs),
+            ("-osx", Os::MacOs),
+        ];
+
+        let name = name.to_lowercase();
+        PLATFORMS
+            .iter()
+            .filter_map(|(platform, os)| Some((name.rfind(platform)?, *os)))
+            .max_by_key(|(position, _)| *position)
+            .map(|(_, os)| os)
+    }
+}
+
+impl std::fmt::Display for Os {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Os::Linux => write!(f, "Linux"),
+            Os::Windows => write!(f, "Windows"),
+            Os::MacOs => write!(f, "macOS"),
+        }
+    }
+}
+
+/// The processor architecture a package was built for.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Arch {
+    X86_64,
+    Arm64,
+    /// 32-bit x86, which only old releases were built for.
+    X86,
+}
+
+impl Arch {
+    pub const ALL: [Arch; 3] = [Arch::X86_64, Arch::Arm64, Arch::X86];
+
+    pub fn current() -> Self {
+        if cfg!(target_arch = "aarch64") {
+            Arch::Arm64
+        } else if cfg!(target_arch = "x86") {
+            Arch::X86
+        } else {
+            Arch::X86_64
+        }
+    }
+
+    /// The architecture an archive is for, going by its file name. Names that don't mention
+    /// one, like those of most old releases, are for x86_64.
+    pub fn from_file_name(name: &str) -> Self {
+        if name.contains("arm64") || name.contains("aarch64") {
+            Arch::Arm64
+        } else if ["i386", "i686", "win32", "windows32", "linux32"]
+            .iter()
+            .any(|x86| name.contains(x86))
+        {
+            Arch::X86
+        } else {
+            Arch::X86_64
+        }
+    }
+}
+
+/// Packages from before architectures were told apart were all fetched for x86_64.
+impl Default for Arch {
+    fn default() -> Self {
+        Arch::X86_64
+    }
+}
+
+impl std::fmt::Display for Arch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Arch::X86_64 => write!(f, "x86_64"),
+            Arch::Arm64 => write!(f, "arm64"),
+            Arch::X86 => write!(f, "x86"),
+        }
+    }
+}
+
+/// An OS and architecture combination that packages are fetched for, see `Settings::targets`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Target {
+    pub os: Os,
+    pub arch: Arch,
+}
+
+impl Target {
+    pub fn current() -> Self {
+        Target {
+            os: Os::current(),
+            arch: Arch::current(),
+        }
+    }
+
+    /// Whether packages for this target should be fetched.
+    pub fn is_tracked(&self) -> bool {
+        get_setting().targets.contains(self)
+    }
+
+    /// The target an archive is for, going by its file name, if it's one that's tracked.
+    pub fn of_tracked_file(name: &str) -> Option<Target> {
+        let target = Target {
+            os: Os::from_file_name(name)?,
+            arch: Arch::from_file_name(name),
+        };
+        target.is_tracked().then_some(target)
+    }
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.os, self.arch)
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Change {
     pub text: String,
     pub url: String,
 }
 
+/// Which changelog is expanded in a package's card.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChangelogView {
+    Own,
+    NewerReleases,
+}
+
 #[derive(Clone, Debug)]
 pub enum PackageState {
     Fetched,
-    Downloading { progress: f32 },
-    Extracting { progress: f32 },
+    /// Waiting in the download queue for a free slot.
+    Queued,
+    Paused,
+    Downloading {
+        status: DownloadStatus,
+    },
+    /// How many bytes of the archive were extracted so far, out of its whole size.
+    Extracting {
+        extracted: u64,
+        total: u64,
+    },
     Installed,
-    Errored { message: String },
+    Errored {
+        message: String,
+    },
 }
 
 impl Default for PackageState {
@@ -201,6 +500,7 @@ pub enum BuildType {
         archive: bool,
         lts: bool,
     },
+    Custom,
     None,
 }
 
@@ -244,6 +544,7 @@ impl BuildType {
                 Build::Lts => *lts = true,
                 _ => (),
             },
+            BuildType::Custom => (),
             BuildType::None => match build {
                 Build::DailyLatest(name) => {
                     *self = BuildType::Daily {
@@ -308,6 +609,7 @@ impl BuildType {
                         lts: true,
                     };
                 }
+                Build::Custom => *self = BuildType::Custom,
             },
         }
     }
@@ -384,7 +686,51 @@ impl std::fmt::Display for BuildType {
 
                 write!(f, "{}", text)
             }
+            BuildType::Custom => write!(f, "Custom"),
             BuildType::None => unreachable!("uninitialised build type"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_archive_without_url() {
+        let package = Package::default();
+        assert_eq!(package.archive_path(), None);
+        assert_eq!(package.cached_archive(), None);
+    }
+
+    #[test]
+    fn os_from_file_name() {
+        for (name, os) in [
+            ("blender-4.1.1-linux-x64.tar.xz", Some(Os::Linux)),
+            (
+                "blender-2.49b-linux-glibc236-py26-i386.tar.bz2",
+                Some(Os::Linux),
+            ),
+            ("blender-4.1.1-windows-x64.zip", Some(Os::Windows)),
+            ("blender-2.79b-windows64.zip", Some(Os::Windows)),
+            ("blender-2.49b-win64-python26.zip", Some(Os::Windows)),
+            ("blender-2.57b-win32.zip", Some(Os::Windows)),
+            ("blender-4.1.1-macos-arm64.dmg", Some(Os::MacOs)),
+            ("blender-2.79b-macOS-10.6.dmg", Some(Os::MacOs)),
+            ("blender-2.64a-release-OSX_10.6-x86_64.zip", Some(Os::MacOs)),
+            (
+                "blender-4.2.0-alpha+main.a1b2c3d4e5f6-darwin.arm64-release.dmg",
+                Some(Os::MacOs),
+            ),
+            (
+                "blender-4.2.0-alpha+fix-linux-wintab.a1b2c3d4e5f6-windows.amd64-release.zip",
+                Some(Os::Windows),
+            ),
+            ("blender-2.49b-solaris-2.8-sparc.tar.gz", None),
+            ("blender-2.49b-freebsd-7.2-i386.tar.gz", None),
+            ("blender-2.79b.tar.gz", None),
+        ] {
+            assert_eq!(Os::from_file_name(name), os, "{}", name);
+        }
+    }
+}
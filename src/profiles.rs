@@ -0,0 +1,223 @@
+use crate::settings::{get_setting, save_settings, set_setting, Settings};
+use directories_next::BaseDirs;
+use fs_extra::dir::{copy, CopyOptions};
+use std::{
+    fs::{create_dir_all, read_dir, remove_dir_all},
+    path::{Path, PathBuf},
+    process::Command,
+};
+use versions::Versioning;
+
+/// The user directories Blender reads from, each of which a profile has its own copy of.
+const USER_DIRS: [(&str, &str); 3] = [
+    ("config", "BLENDER_USER_CONFIG"),
+    ("scripts", "BLENDER_USER_SCRIPTS"),
+    ("datafiles", "BLENDER_USER_DATAFILES"),
+];
+
+/// A named set of Blender user directories, which packages and recent files can be launched with
+/// instead of the configuration Blender would normally use.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl Profile {
+    /// All profiles, which are simply the directories inside `profiles_dir`.
+    pub fn list() -> Vec<Profile> {
+        let mut profiles = Vec::new();
+
+        if let Ok(entries) = read_dir(&get_setting().profiles_dir) {
+            for entry in entries.flatten() {
+                if entry.path().is_dir() {
+                    profiles.push(Profile {
+                        name: entry.file_name().to_string_lossy().to_string(),
+                        path: entry.path(),
+                    });
+                }
+            }
+        }
+
+        profiles.sort_by(|a, b| a.name.cmp(&b.name));
+        profiles
+    }
+
+    pub fn get(name: &str) -> Option<Profile> {
+        Profile::list()
+            .into_iter()
+            .find(|profile| profile.name == name)
+    }
+
+    pub fn create(name: &str) -> Result<Profile, String> {
+        let name = name.trim();
+
+        if name.is_empty() {
+            return Err(String::from("the name can't be empty"));
+        } else if name.contains(['/', '\\', ':']) || name.starts_with('.') {
+            return Err(format!("'{}' can't be used as a directory name", name));
+        }
+
+        let profile = Profile {
+            name: name.to_string(),
+            path: get_setting().profiles_dir.join(name),
+        };
+
+        if profile.path.exists() {
+            return Err(format!("a profile named '{}' already exists", name));
+        }
+
+        for (dir, _) in USER_DIRS {
+            create_dir_all(profile.path.join(dir)).map_err(|e| e.to_string())?;
+        }
+
+        Ok(profile)
+    }
+
+    /// Create a profile starting off with the contents of an existing Blender configuration,
+    /// which is a directory like `~/.config/blender/3.2` containing `config`, `scripts` and so on.
+    pub fn clone_from(name: &str, source: &Path) -> Result<Profile, String> {
+        let profile = Profile::create(name)?;
+        let mut options = CopyOptions::new();
+        options.content_only = true;
+        options.overwrite = true;
+
+        for (dir, _) in USER_DIRS {
+            let source_dir = source.join(dir);
+
+            if source_dir.is_dir() {
+                if let Err(e) = copy(&source_dir, profile.path.join(dir), &options) {
+                    profile.delete();
+                    return Err(e.to_string());
+                }
+            }
+        }
+
+        Ok(profile)
+    }
+
+    /// Remove the profile's files and stop using it anywhere it was chosen.
+    pub fn delete(&self) {
+        let _ = remove_dir_all(&self.path);
+
+        forget(&mut set_setting(), &self.name);
+        save_settings();
+
+        println!("Deleted profile: {}", self.name);
+    }
+
+    /// Point Blender at this profile's directories instead of its usual ones.
+    pub fn apply(&self, cmd: &mut Command) {
+        cmd.env("BLENDER_USER_RESOURCES", &self.path);
+
+        for (dir, variable) in USER_DIRS {
+            cmd.env(variable, self.path.join(dir));
+        }
+    }
+}
+
+/// Go back to Blender's default profile wherever the named one was chosen.
+fn forget(settings: &mut Settings, name: &str) {
+    settings
+        .package_profiles
+        .retain(|_, profile| profile != name);
+    for recent_file in settings.recent_files.values_mut() {
+        if recent_file.profile.as_deref() == Some(name) {
+            recent_file.profile = None;
+        }
+    }
+    for rule in &mut settings.association_rules {
+        if rule.profile.as_deref() == Some(name) {
+            rule.profile = None;
+        }
+    }
+}
+
+/// Configurations of Blender itself, found where it keeps them by default.
+/// Returns the version they belong to and their path, newest first.
+pub fn find_blender_configs() -> Vec<(String, PathBuf)> {
+    let base_dirs = match BaseDirs::new() {
+        Some(base_dirs) => base_dirs,
+        None => return Vec::new(),
+    };
+
+    let root = if cfg!(target_os = "linux") {
+        base_dirs.config_dir().join("blender")
+    } else if cfg!(target_os = "windows") {
+        base_dirs
+            .config_dir()
+            .join("Blender Foundation")
+            .join("Blender")
+    } else if cfg!(target_os = "macos") {
+        base_dirs.config_dir().join("Blender")
+    } else {
+        unreachable!("Unsupported OS");
+    };
+
+    let mut configs = Vec::new();
+
+    if let Ok(entries) = read_dir(root) {
+        for entry in entries.flatten() {
+            let version = entry.file_name().to_string_lossy().to_string();
+
+            if entry.path().is_dir() && version.starts_with(|c: char| c.is_ascii_digit()) {
+                configs.push((version, entry.path()));
+            }
+        }
+    }
+
+    configs.sort_by(|a, b| {
+        Versioning::new(&b.0)
+            .cmp(&Versioning::new(&a.0))
+            .then(b.0.cmp(&a.0))
+    });
+    configs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{association::AssociationRule, gui::tabs::recent_files::RecentFile};
+
+    #[test]
+    fn forget_deleted_profile() {
+        let mut settings = Settings::default();
+        let rule = |pattern: &str, profile: &str| AssociationRule {
+            pattern: pattern.to_string(),
+            query: "lts".parse().unwrap(),
+            profile: Some(profile.to_string()),
+        };
+        settings
+            .package_profiles
+            .insert(String::from("blender-3.6.5"), String::from("studio"));
+        settings
+            .package_profiles
+            .insert(String::from("blender-4.2.0"), String::from("personal"));
+        let mut recent_file =
+            RecentFile::new(PathBuf::from("scene.blend"), String::from("blender-3.6.5"));
+        recent_file.profile = Some(String::from("studio"));
+        settings
+            .recent_files
+            .insert(PathBuf::from("scene.blend"), recent_file);
+        settings.association_rules = vec![
+            rule("~/studio/**", "studio"),
+            rule("~/personal/**", "personal"),
+        ];
+
+        forget(&mut settings, "studio");
+
+        assert_eq!(
+            settings.package_profiles.keys().collect::<Vec<_>>(),
+            ["blender-4.2.0"]
+        );
+        assert_eq!(
+            settings.recent_files[Path::new("scene.blend")].profile,
+            None
+        );
+        assert_eq!(settings.association_rules[0].profile, None);
+        assert_eq!(
+            settings.association_rules[1].profile.as_deref(),
+            Some("personal")
+        );
+    }
+}
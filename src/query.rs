@@ -0,0 +1,367 @@
+use crate::package::{Build, Package, PackageState};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// A way of picking packages that's shorter than their full names and keeps working as newer
+/// ones come out, like "3.6", "4.2.*", "lts", "latest-stable", "daily:main" or
+/// "experimental:cycles". Anything else is taken as a package's exact name.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum PackageQuery {
+    /// Leading numbers of the version, where "4.2" and "4.2.*" are the same. Only releases and
+    /// custom packages match, since builder builds are picked with their own queries.
+    Version(Vec<u32>),
+    /// Stable releases, including the archived ones.
+    Stable,
+    /// Only the stable releases of the current series.
+    LatestStable,
+    Lts,
+    /// Daily builds, optionally of a branch or release cycle, like "main" or "alpha".
+    Daily(Option<String>),
+    Experimental(Option<String>),
+    Patch(Option<String>),
+    Name(String),
+}
+
+impl FromStr for PackageQuery {
+    type Err = String;
+
+    fn from_str(query: &str) -> Result<Self, Self::Err> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Err(String::from("the query can't be empty"));
+        }
+
+        let (kind, filter) = match query.split_once(':') {
+            Some((kind, filter)) => (kind, Some(filter.trim()).filter(|f| !f.is_empty())),
+            None => (query, None),
+        };
+        let filter = filter.map(str::to_lowercase);
+
+        match (kind.to_lowercase().as_str(), &filter) {
+            ("stable", None) => return Ok(PackageQuery::Stable),
+            ("latest-stable", None) => return Ok(PackageQuery::LatestStable),
+            ("lts", None) => return Ok(PackageQuery::Lts),
+            ("daily", _) => return Ok(PackageQuery::Daily(filter)),
+            ("experimental", _) => return Ok(PackageQuery::Experimental(filter)),
+            ("patch", _) => return Ok(PackageQuery::Patch(filter)),
+            _ => (),
+        }
+
+        let numbers: Option<Vec<u32>> = query
+            .trim_end_matches(".*")
+            .split('.')
+            .map(|number| number.parse().ok())
+            .collect();
+
+        Ok(match numbers {
+            Some(numbers) => PackageQuery::Version(numbers),
+            None => PackageQuery::Name(query.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for PackageQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = |f: &mut fmt::Formatter<'_>, kind, filter: &Option<String>| match filter {
+            Some(filter) => write!(f, "{}:{}", kind, filter),
+            None => write!(f, "{}", kind),
+        };
+
+        match self {
+            PackageQuery::Version(numbers) => {
+                write!(
+                    f,
+                    "{}",
+                    numbers
+                        .iter()
+                        .map(u32::to_string)
+                        .collect::<Vec<_>>()
+                        .join(".")
+                )
+            }
+            PackageQuery::Stable => write!(f, "stable"),
+            PackageQuery::LatestStable => write!(f, "latest-stable"),
+            PackageQuery::Lts => write!(f, "lts"),
+            PackageQuery::Daily(filter) => kind(f, "daily", filter),
+            PackageQuery::Experimental(filter) => kind(f, "experimental", filter),
+            PackageQuery::Patch(filter) => kind(f, "patch", filter),
+            PackageQuery::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl TryFrom<String> for PackageQuery {
+    type Error = String;
+
+    fn try_from(query: String) -> Result<Self, Self::Error> {
+        query.parse()
+    }
+}
+
+impl From<PackageQuery> for String {
+    fn from(query: PackageQuery) -> Self {
+        query.to_string()
+    }
+}
+
+/// What a query resolved to, preferring packages that are already installed.
+#[derive(Debug, PartialEq)]
+pub enum Resolved<'a> {
+    Installed(&'a Package),
+    Installable(&'a Package),
+}
+
+impl PackageQuery {
+    pub fn matches(&self, package: &Package) -> bool {
+        // A filter like "cycles" also covers the "cycles-light" branch.
+        let fits = |filter: &str, name: &str| {
+            let name = name.to_lowercase();
+            name == filter
+                || name
+                    .strip_prefix(filter)
+                    .is_some_and(|rest| rest.starts_with('-'))
+        };
+
+        // Builder packages carry their branch in the name, like "blender-4.3.0-alpha+main.61ddf9f0a0e2".
+        let filter_matches = |filter: &Option<String>, label: &str| match filter {
+            Some(filter) => {
+                fits(filter, label)
+                    || package
+                        .name
+                        .split_once('+')
+                        .and_then(|(_, rest)| rest.split('.').next())
+                        .is_some_and(|branch| fits(filter, branch))
+            }
+            None => true,
+        };
+
+        match (self, &package.build) {
+            (
+                PackageQuery::Version(numbers),
+                Build::StableLatest | Build::StableArchive | Build::Lts | Build::Custom,
+            ) => numbers
+                .iter()
+                .enumerate()
+                .all(|(index, number)| package.version.nth(index) == Some(*number)),
+            (PackageQuery::Stable, Build::StableLatest | Build::StableArchive) => true,
+            (PackageQuery::LatestStable, Build::StableLatest) => true,
+            (PackageQuery::Lts, Build::Lts) => true,
+            (
+                PackageQuery::Daily(filter),
+                Build::DailyLatest(label) | Build::DailyArchive(label),
+            )
+            | (
+                PackageQuery::Experimental(filter),
+                Build::ExperimentalLatest(label) | Build::ExperimentalArchive(label),
+            )
+            | (
+                PackageQuery::Patch(filter),
+                Build::PatchLatest(label) | Build::PatchArchive(label),
+            ) => filter_matches(filter, label),
+            (PackageQuery::Name(name), _) => package.name == *name,
+            _ => false,
+        }
+    }
+
+    /// The newest matching package that runs on this machine, by version and then by date. An
+    /// exact name picks that package whatever it runs on, like the list command shows them.
+    pub fn newest<'a>(
+        &self,
+        packages: impl IntoIterator<Item = &'a Package>,
+    ) -> Option<&'a Package> {
+        packages
+            .into_iter()
+            .filter(|package| {
+                (package.runs_here() || matches!(self, PackageQuery::Name(_)))
+                    && self.matches(package)
+            })
+            .max_by(|a, b| a.version.cmp(&b.version).then(a.date.cmp(&b.date)))
+    }
+
+    /// The newest match out of the installed and all the known packages, and whether it's
+    /// already installed.
+    pub fn resolve<'a>(
+        &self,
+        installed: impl IntoIterator<Item = &'a Package>,
+        known: impl IntoIterator<Item = &'a Package>,
+    ) -> Option<Resolved<'a>> {
+        let installed: Vec<&Package> = installed.into_iter().collect();
+        let newest = self.newest(installed.iter().copied().chain(known))?;
+
+        if matches!(newest.state, PackageState::Installed) || installed.contains(&newest) {
+            Some(Resolved::Installed(newest))
+        } else {
+            Some(Resolved::Installable(newest))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers;
+    use chrono::NaiveDate;
+
+    fn package(name: &str, version: &str, build: Build, day: u32) -> Package {
+        Package {
+            build,
+            date: NaiveDate::from_ymd_opt(2024, 7, day)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap(),
+            ..test_helpers::package(name, version)
+        }
+    }
+
+    fn query(query: &str) -> PackageQuery {
+        query.parse().unwrap()
+    }
+
+    fn packages() -> Vec<Package> {
+        vec![
+            package("blender-3.6.5-linux-x64", "3.6.5", Build::Lts, 1),
+            package("blender-3.6.13-linux-x64", "3.6.13", Build::Lts, 2),
+            package("blender-4.1.1-linux-x64", "4.1.1", Build::StableArchive, 3),
+            package("blender-4.2.0-linux-x64", "4.2.0", Build::StableLatest, 4),
+            package(
+                "blender-4.3.0-alpha+main.61ddf9f0a0e2-linux.x86_64-release",
+                "4.3.0",
+                Build::DailyLatest(String::from("Alpha")),
+                5,
+            ),
+            package(
+                "blender-4.3.0-alpha+main.0ee6bff9d3d1-linux.x86_64-release",
+                "4.3.0",
+                Build::DailyArchive(String::from("Alpha")),
+                6,
+            ),
+            package(
+                "blender-4.2.1-candidate+v42.a5ec4bc2d3c5-linux.x86_64-release",
+                "4.2.1",
+                Build::DailyLatest(String::from("Candidate")),
+                7,
+            ),
+            package(
+                "blender-4.3.0-alpha+cycles-light.2b2b7dd9a1f3-linux.x86_64-release",
+                "4.3.0",
+                Build::ExperimentalLatest(String::from("cycles-light")),
+                8,
+            ),
+        ]
+    }
+
+    fn newest(query_text: &str) -> Option<String> {
+        query(query_text)
+            .newest(&packages())
+            .map(|package| package.name.clone())
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(query("3.6"), PackageQuery::Version(vec![3, 6]));
+        assert_eq!(query("4.2.*"), PackageQuery::Version(vec![4, 2]));
+        assert_eq!(query(" 4 "), PackageQuery::Version(vec![4]));
+        assert_eq!(query("LTS"), PackageQuery::Lts);
+        assert_eq!(query("stable"), PackageQuery::Stable);
+        assert_eq!(query("latest-stable"), PackageQuery::LatestStable);
+        assert_eq!(query("daily"), PackageQuery::Daily(None));
+        assert_eq!(
+            query("daily:Main"),
+            PackageQuery::Daily(Some(String::from("main")))
+        );
+        assert_eq!(
+            query("experimental:cycles"),
+            PackageQuery::Experimental(Some(String::from("cycles")))
+        );
+        assert_eq!(
+            query("blender-4.2.0-linux-x64"),
+            PackageQuery::Name(String::from("blender-4.2.0-linux-x64"))
+        );
+        assert_eq!(query("4.x"), PackageQuery::Name(String::from("4.x")));
+        assert!("  ".parse::<PackageQuery>().is_err());
+
+        for text in [
+            "3.6",
+            "lts",
+            "stable",
+            "latest-stable",
+            "daily:main",
+            "experimental",
+            "my-build",
+        ] {
+            assert_eq!(query(text).to_string(), text);
+        }
+    }
+
+    #[test]
+    fn pick_newest() {
+        assert_eq!(newest("3.6").as_deref(), Some("blender-3.6.13-linux-x64"));
+        assert_eq!(newest("3.6.5").as_deref(), Some("blender-3.6.5-linux-x64"));
+        assert_eq!(newest("lts").as_deref(), Some("blender-3.6.13-linux-x64"));
+        assert_eq!(
+            newest("latest-stable").as_deref(),
+            Some("blender-4.2.0-linux-x64")
+        );
+        // The newer 4.2.1 and 4.3.0 daily builds don't count as versions.
+        assert_eq!(newest("4.2.*").as_deref(), Some("blender-4.2.0-linux-x64"));
+        assert_eq!(newest("4").as_deref(), Some("blender-4.2.0-linux-x64"));
+        assert_eq!(newest("4.3"), None);
+        assert_eq!(
+            newest("daily:main").as_deref(),
+            Some("blender-4.3.0-alpha+main.0ee6bff9d3d1-linux.x86_64-release")
+        );
+        assert_eq!(
+            newest("daily:candidate").as_deref(),
+            Some("blender-4.2.1-candidate+v42.a5ec4bc2d3c5-linux.x86_64-release")
+        );
+        assert_eq!(
+            newest("experimental:cycles-light").as_deref(),
+            Some("blender-4.3.0-alpha+cycles-light.2b2b7dd9a1f3-linux.x86_64-release")
+        );
+        assert_eq!(
+            newest("experimental:cycles").as_deref(),
+            Some("blender-4.3.0-alpha+cycles-light.2b2b7dd9a1f3-linux.x86_64-release")
+        );
+        assert_eq!(newest("experimental:cyc"), None);
+        assert_eq!(newest("patch"), None);
+        assert_eq!(
+            newest("blender-4.1.1-linux-x64").as_deref(),
+            Some("blender-4.1.1-linux-x64")
+        );
+    }
+
+    #[test]
+    fn resolve_newest() {
+        let mut known = packages();
+        known[0].state = PackageState::Installed;
+        known[2].state = PackageState::Installed;
+        let installed = vec![known[0].clone(), known[2].clone()];
+
+        // Older installed matches don't hide newer ones.
+        assert_eq!(
+            query("3.6").resolve(&installed, &known),
+            Some(Resolved::Installable(&known[1]))
+        );
+        assert_eq!(
+            query("latest-stable").resolve(&installed, &known),
+            Some(Resolved::Installable(&known[3]))
+        );
+        assert_eq!(
+            query("3.6.5").resolve(&installed, &known),
+            Some(Resolved::Installed(&installed[0]))
+        );
+        assert_eq!(
+            query("4.1").resolve(&installed, &known),
+            Some(Resolved::Installed(&installed[1]))
+        );
+
+        known[1].state = PackageState::Installed;
+        let installed = vec![known[0].clone(), known[1].clone()];
+        assert!(matches!(
+            query("3.6").resolve(&installed, &known),
+            Some(Resolved::Installed(package)) if package.name == "blender-3.6.13-linux-x64"
+        ));
+        assert_eq!(query("2.79").resolve(&installed, &known), None);
+    }
+}
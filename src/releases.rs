@@ -8,6 +8,8 @@ pub mod patch_archive;
 pub mod patch_latest;
 pub mod stable_archive;
 pub mod stable_latest;
+#[cfg(test)]
+mod tests;
 use self::{
     daily_archive::DailyArchive, daily_latest::DailyLatest,
     experimental_archive::ExperimentalArchive, experimental_latest::ExperimentalLatest,
@@ -15,8 +17,10 @@ use self::{
     stable_archive::StableArchive, stable_latest::StableLatest,
 };
 use crate::{
-    helpers::{get_document, get_file_stem, ReturnOption},
-    package::{Build, Os, Package, PackageState, PackageStatus},
+    error::{AblavemaError, OrParseError, Result},
+    helpers::{get_document, get_file_stem, site_url, ReturnOption},
+    http,
+    package::{Arch, Build, Os, Package, PackageState, PackageStatus, Target},
     settings::{get_setting, init_settings, save_settings, set_setting, CAN_CONNECT},
 };
 use async_trait::async_trait;
@@ -37,6 +41,14 @@ use std::{
 };
 use versions::Versioning;
 
+/// How checking each source for new packages went, by the name of the source.
+pub type CheckResults = Vec<(&'static str, Result<bool>)>;
+
+/// Whether any of the sources that were checked had new packages.
+pub fn found_new_packages(results: &CheckResults) -> bool {
+    results.iter().any(|(_, result)| matches!(result, Ok(true)))
+}
+
 #[derive(Debug, Default)]
 pub struct Releases {
     pub daily_latest: DailyLatest,
@@ -109,10 +121,11 @@ impl Releases {
 
         self.lts.refresh_state(&self.installed);
         self.lts.refresh_status(get_setting().update_lts);
+        self.lts.refresh_newer_changes();
     }
 
-    /// Check for new packages. This returns a tuple where the first item is a boolean
-    /// that indicates whether there were any new packages found.
+    /// Check for new packages. This returns a tuple where the first item is how checking each
+    /// source went, so a source that failed doesn't keep the others from updating.
     pub async fn check_updates(
         packages: (
             DailyLatest,
@@ -122,7 +135,7 @@ impl Releases {
             Lts,
         ),
     ) -> (
-        bool,
+        CheckResults,
         DailyLatest,
         ExperimentalLatest,
         PatchLatest,
@@ -140,48 +153,41 @@ impl Releases {
             mut lts,
         ) = packages;
 
-        let mut updated_daily_latest = false;
+        let mut results = CheckResults::new();
+
         if get_setting().update_daily_latest && daily_latest.get_db_path().exists() {
-            let (updated, fetched_daily_latest) = DailyLatest::check_updates(daily_latest).await;
-            updated_daily_latest = updated;
+            let (result, fetched_daily_latest) = DailyLatest::check_updates(daily_latest).await;
+            results.push((DailyLatest::NAME, result));
             daily_latest = fetched_daily_latest;
         }
 
-        let mut updated_experimental_latest = false;
         if get_setting().update_experimental_latest && experimental_latest.get_db_path().exists() {
-            let (updated, fetched_experimental_latest) =
+            let (result, fetched_experimental_latest) =
                 ExperimentalLatest::check_updates(experimental_latest).await;
-            updated_experimental_latest = updated;
+            results.push((ExperimentalLatest::NAME, result));
             experimental_latest = fetched_experimental_latest;
         }
 
-        let mut updated_patch_latest = false;
         if get_setting().update_patch_latest && patch_latest.get_db_path().exists() {
-            let (updated, fetched_patch_latest) = PatchLatest::check_updates(patch_latest).await;
-            updated_patch_latest = updated;
+            let (result, fetched_patch_latest) = PatchLatest::check_updates(patch_latest).await;
+            results.push((PatchLatest::NAME, result));
             patch_latest = fetched_patch_latest;
         }
 
-        let mut updated_stable_latest = false;
         if get_setting().update_stable_latest && stable_latest.get_db_path().exists() {
-            let (updated, fetched_stable_latest) = StableLatest::check_updates(stable_latest).await;
-            updated_stable_latest = updated;
+            let (result, fetched_stable_latest) = StableLatest::check_updates(stable_latest).await;
+            results.push((StableLatest::NAME, result));
             stable_latest = fetched_stable_latest;
         }
 
-        let mut updated_lts = false;
         if get_setting().update_lts && lts.get_db_path().exists() {
-            let (updated, fetched_lts) = Lts::check_updates(lts).await;
-            updated_lts = updated;
+            let (result, fetched_lts) = Lts::check_updates(lts).await;
+            results.push((Lts::NAME, result));
             lts = fetched_lts;
         }
 
         (
-            updated_daily_latest
-                || updated_experimental_latest
-                || updated_patch_latest
-                || updated_stable_latest
-                || updated_lts,
+            results,
             daily_latest,
             experimental_latest,
             patch_latest,
@@ -213,7 +219,7 @@ impl Releases {
     pub fn add_new_packages(
         &mut self,
         packages: (
-            bool,
+            CheckResults,
             DailyLatest,
             ExperimentalLatest,
             PatchLatest,
@@ -267,6 +273,25 @@ impl Releases {
         }
     }
 
+    /// Add a custom package, making sure its name doesn't clash with any other package.
+    pub fn register_custom_package(&mut self, package: Package) -> Result<(), String> {
+        if self
+            .build_vec()
+            .iter()
+            .chain(self.installed.iter())
+            .any(|a_package| a_package.name == package.name)
+        {
+            return Err(format!("a package named '{}' already exists", package.name));
+        }
+
+        println!("Registered: {}", package.name);
+        set_setting().custom_packages.push(package);
+        save_settings();
+        self.sync();
+
+        Ok(())
+    }
+
     pub fn build_vec(&self) -> Vec<Package> {
         let mut index = 0;
         let mut packages: Vec<Package> = Vec::new();
@@ -281,6 +306,11 @@ impl Releases {
             .chain(self.stable_latest.iter())
             .chain(self.stable_archive.iter())
             .chain(self.lts.iter())
+            .chain(
+                self.installed
+                    .iter()
+                    .filter(|package| package.build == Build::Custom),
+            )
         {
             match packages
                 .iter_mut()
@@ -322,21 +352,27 @@ pub trait ReleaseType:
     + ops::Deref<Target = Vec<Package>>
     + ops::DerefMut<Target = Vec<Package>>
 {
-    async fn fetch() -> Self;
+    /// How the source is called in the interface, like when reporting that it failed.
+    const NAME: &'static str;
 
-    async fn check_updates(mut packages: Self) -> (bool, Self) {
+    async fn fetch() -> Result<Self>;
+
+    /// Returns whether new packages were found along with the packages,
+    /// which are given back untouched if fetching failed.
+    async fn check_updates(mut packages: Self) -> (Result<bool>, Self) {
         match packages.get_new_packages().await {
-            Some(new_packages) => {
+            Ok(Some(new_packages)) => {
                 packages.add_new_packages(new_packages);
-                packages.save();
-                (true, packages)
+                let saved = packages.save();
+                (saved.map(|_| true), packages)
             }
-            None => (false, packages),
+            Ok(None) => (Ok(false), packages),
+            Err(e) => (Err(e), packages),
         }
     }
 
-    async fn get_new_packages(&self) -> Option<Self> {
-        let mut fetched_packages = Self::fetch().await;
+    async fn get_new_packages(&self) -> Result<Option<Self>> {
+        let mut fetched_packages = Self::fetch().await?;
         let mut new_packages = Self::default();
 
         for package in &mut *fetched_packages {
@@ -346,9 +382,9 @@ pub trait ReleaseType:
         }
 
         if new_packages.is_empty() {
-            None
+            Ok(None)
         } else {
-            Some(new_packages)
+            Ok(Some(new_packages))
         }
     }
 
@@ -368,6 +404,7 @@ pub trait ReleaseType:
             if installed.contains(package) {
                 package.state = PackageState::Installed;
             }
+            package.cached = package.cached_archive().is_some();
         }
     }
 
@@ -419,7 +456,8 @@ pub trait ReleaseType:
                     Build::DailyArchive(_)
                     | Build::ExperimentalArchive(_)
                     | Build::PatchArchive(_)
-                    | Build::StableArchive => {
+                    | Build::StableArchive
+                    | Build::Custom => {
                         break;
                     }
                 }
@@ -432,6 +470,7 @@ pub trait ReleaseType:
                     if let Some(package) = self.iter_mut().find(|package| {
                         installed_package.version == package.version
                             && installed_package.build == package.build
+                            && installed_package.target() == package.target()
                     }) {
                         if package.date > installed_package.date {
                             package.status = PackageStatus::Update;
@@ -439,10 +478,10 @@ pub trait ReleaseType:
                     }
                 }
                 Build::StableLatest => {
-                    if let Some(package) = self
-                        .iter_mut()
-                        .find(|package| installed_package.build == package.build)
-                    {
+                    if let Some(package) = self.iter_mut().find(|package| {
+                        installed_package.build == package.build
+                            && installed_package.target() == package.target()
+                    }) {
                         if package.date > installed_package.date {
                             package.status = PackageStatus::Update;
                         }
@@ -453,6 +492,7 @@ pub trait ReleaseType:
                         installed_package.version.nth(0).unwrap() == package.version.nth(0).unwrap()
                             && installed_package.version.nth(1).unwrap()
                                 == package.version.nth(1).unwrap()
+                            && installed_package.target() == package.target()
                     }) {
                         if package.date > installed_package.date {
                             package.status = PackageStatus::Update;
@@ -462,7 +502,8 @@ pub trait ReleaseType:
                 Build::DailyArchive(_)
                 | Build::ExperimentalArchive(_)
                 | Build::PatchArchive(_)
-                | Build::StableArchive => {
+                | Build::StableArchive
+                | Build::Custom => {
                     break;
                 }
             }
@@ -471,6 +512,7 @@ pub trait ReleaseType:
 
     /// This method tends to temporarily ban the user due to the large amount of requests sent
     /// over a short period of time, so it shouldn't be used in places like .sync().
+    /// The requests are capped and backed off from by `http::send`, but it's still a lot of them.
     /// It's better to check the availability of a package on Un/Installing.
     async fn remove_dead_packages(&mut self) {
         // TODO: Figure out what to do with dead packages.
@@ -479,18 +521,17 @@ pub trait ReleaseType:
         if CAN_CONNECT.load(Ordering::Relaxed) {
             let mut checkables = Vec::new();
             for (index, package) in self.iter().enumerate() {
-                if !matches!(package.state, PackageState::Installed { .. }) {
-                    checkables.push((index, package.url.clone()));
+                if !matches!(package.state, PackageState::Installed { .. }) && !package.cached {
+                    checkables.push((index, package.urls()[0].clone()));
                 }
             }
 
             let mut handles = Vec::new();
             for (index, url) in checkables {
                 let handle = tokio::task::spawn(async move {
-                    if reqwest::get(&url).await.unwrap().status().is_client_error() {
-                        Some(index)
-                    } else {
-                        None
+                    match http::send(http::client().get(&url)).await {
+                        Ok(response) if response.status().is_client_error() => Some(index),
+                        _ => None,
                     }
                 });
                 handles.push(handle);
@@ -512,10 +553,13 @@ pub trait ReleaseType:
 
     fn get_db_path(&self) -> PathBuf;
 
-    fn save(&self) {
-        let mut file = File::create(self.get_db_path()).unwrap();
-        let settings = to_string_pretty(&self, PrettyConfig::new()).unwrap();
-        file.write_all(settings.as_bytes()).unwrap();
+    fn save(&self) -> Result<()> {
+        let path = self.get_db_path();
+        let database = to_string_pretty(&self, PrettyConfig::new())
+            .map_err(|e| AblavemaError::database(&path, e))?;
+        File::create(&path)
+            .and_then(|mut file| file.write_all(database.as_bytes()))
+            .map_err(|e| AblavemaError::database(&path, e))
     }
 
     fn load(&mut self) {
@@ -523,9 +567,8 @@ pub trait ReleaseType:
             match from_str(&db) {
                 Ok(self_db) => *self = self_db,
                 Err(e) => eprintln!(
-                    "Error reading database file `{}` with error:\n{}",
-                    self.get_db_path().file_name().unwrap().to_str().unwrap(),
-                    e
+                    "Error reading {}",
+                    AblavemaError::database(self.get_db_path(), e)
                 ),
             }
         }
@@ -550,104 +593,124 @@ pub enum BuilderBuild {
 }
 
 impl BuilderBuild {
-    pub async fn fetch(&self) -> Vec<Package> {
-        let url = match self {
-            BuilderBuild::DailyLatest => "https://builder.blender.org/download/daily/",
-            BuilderBuild::DailyArchive => "https://builder.blender.org/download/daily/archive/",
-            BuilderBuild::ExperimentalLatest => {
-                "https://builder.blender.org/download/experimental/"
-            }
-            BuilderBuild::ExperimentalArchive => {
-                "https://builder.blender.org/download/experimental/archive/"
-            }
-            BuilderBuild::PatchLatest => "https://builder.blender.org/download/patch/",
-            BuilderBuild::PatchArchive => "https://builder.blender.org/download/patch/archive/",
+    pub async fn fetch(&self) -> Result<Vec<Package>> {
+        let path = match self {
+            BuilderBuild::DailyLatest => "download/daily/",
+            BuilderBuild::DailyArchive => "download/daily/archive/",
+            BuilderBuild::ExperimentalLatest => "download/experimental/",
+            BuilderBuild::ExperimentalArchive => "download/experimental/archive/",
+            BuilderBuild::PatchLatest => "download/patch/",
+            BuilderBuild::PatchArchive => "download/patch/archive/",
         };
-        let document = get_document(url).await;
+        let url = &site_url("builder.blender.org", path);
+        let document = get_document(url).await?;
         let mut packages = Vec::new();
 
-        let (platform, os) = {
-            if cfg!(target_os = "linux") {
-                ("platform-linux", Os::Linux)
-            } else if cfg!(target_os = "windows") {
-                ("platform-windows", Os::Windows)
-            } else if cfg!(target_os = "macos") {
-                ("platform-darwin", Os::MacOs)
-            } else {
-                unreachable!("Unsupported OS");
-            }
-        };
+        let targets = get_setting().targets.clone();
 
-        let builds_list = document
-            .find(And(Class("builds-list-container"), Class(platform)))
-            .next()
-            .unwrap();
+        for os in Os::ALL
+            .into_iter()
+            .filter(|os| targets.iter().any(|target| target.os == *os))
+        {
+            let platform = match os {
+                Os::Linux => "platform-linux",
+                Os::Windows => "platform-windows",
+                Os::MacOs => "platform-darwin",
+            };
 
-        for build_node in builds_list.find(Class("build-info")) {
-            let url = build_node
-                .find(Name("a"))
+            // Patch and experimental branches often aren't built for every platform.
+            let builds_list = match document
+                .find(And(Class("builds-list-container"), Class(platform)))
                 .next()
-                .unwrap()
-                .attr("href")
-                .unwrap()
-                .to_string();
+            {
+                Some(builds_list) => builds_list,
+                None => continue,
+            };
 
-            if url.ends_with(".sha256") {
-                continue;
-            }
+            let checksum_urls: Vec<&str> = builds_list
+                .find(Class("build-info").descendant(Name("a")))
+                .filter_map(|node| node.attr("href"))
+                .filter(|url| url.ends_with(".sha256"))
+                .collect();
 
-            let name = get_file_stem(&url).to_string();
+            for build_node in builds_list.find(Class("build-info")) {
+                let url = build_node
+                    .find(Name("a"))
+                    .next()
+                    .and_then(|node| node.attr("href"))
+                    .or_parse_error(url, "build without a link")?
+                    .to_string();
 
-            let build_name = build_node.find(Class("build-var")).next().unwrap().text();
-            let build = match self {
-                BuilderBuild::DailyLatest => Build::DailyLatest(build_name),
-                BuilderBuild::DailyArchive => Build::DailyArchive(build_name),
-                BuilderBuild::ExperimentalLatest => Build::ExperimentalLatest(build_name),
-                BuilderBuild::ExperimentalArchive => Build::ExperimentalArchive(build_name),
-                BuilderBuild::PatchLatest => Build::PatchLatest(build_name),
-                BuilderBuild::PatchArchive => Build::PatchArchive(build_name),
-            };
+                if url.ends_with(".sha256") {
+                    continue;
+                }
 
-            let version = Versioning::new(
-                build_node
+                let arch = Arch::from_file_name(&url);
+                if !targets.contains(&Target { os, arch }) {
+                    continue;
+                }
+
+                let checksum_url = format!("{}.sha256", url);
+                let checksum_url = if checksum_urls.contains(&checksum_url.as_str()) {
+                    checksum_url
+                } else {
+                    String::new()
+                };
+
+                let name = get_file_stem(&url).to_string();
+
+                let build_name = build_node
+                    .find(Class("build-var"))
+                    .next()
+                    .or_parse_error(&url, "build without a name")?
+                    .text();
+                let build = match self {
+                    BuilderBuild::DailyLatest => Build::DailyLatest(build_name),
+                    BuilderBuild::DailyArchive => Build::DailyArchive(build_name),
+                    BuilderBuild::ExperimentalLatest => Build::ExperimentalLatest(build_name),
+                    BuilderBuild::ExperimentalArchive => Build::ExperimentalArchive(build_name),
+                    BuilderBuild::PatchLatest => Build::PatchLatest(build_name),
+                    BuilderBuild::PatchArchive => Build::PatchArchive(build_name),
+                };
+
+                let version = build_node
                     .find(Class("build-title"))
                     .next()
-                    .unwrap()
-                    .text()
-                    .split_whitespace()
+                    .and_then(|node| Versioning::new(node.text().split_whitespace().nth(1)?))
+                    .or_parse_error(&url, "build without a version")?;
+
+                let date_without_year = build_node
+                    .find(Class("build-details").descendant(Name("li")))
+                    .nth(0)
+                    .or_parse_error(&url, "build without a date")?
+                    .text();
+                let build_id = build_node
+                    .find(Class("build-details").descendant(Name("li")))
                     .nth(1)
-                    .unwrap(),
-            )
-            .unwrap();
-
-            let date_without_year = build_node
-                .find(Class("build-details").descendant(Name("li")))
-                .nth(0)
-                .unwrap()
-                .text();
-            let build_id = build_node
-                .find(Class("build-details").descendant(Name("li")))
-                .nth(1)
-                .unwrap()
-                .text();
-            let date_string = format!("{}-{}", date_without_year, Utc::today().year());
-            let date = NaiveDateTime::parse_from_str(&date_string, "%B %d, %T-%Y").unwrap();
-
-            let package = Package {
-                version,
-                name,
-                build,
-                date,
-                commit: build_id,
-                url,
-                os,
-                ..Default::default()
-            };
-
-            packages.push(package);
+                    .or_parse_error(&url, "build without a commit")?
+                    .text();
+                let date_string = format!("{}-{}", date_without_year, Utc::today().year());
+                let date = NaiveDateTime::parse_from_str(&date_string, "%B %d, %T-%Y")
+                    .or_parse_error(&url, "build with an unexpected date")?;
+
+                let package = Package {
+                    version,
+                    name,
+                    build,
+                    date,
+                    commit: build_id,
+                    url,
+                    checksum_url,
+                    os,
+                    arch,
+                    ..Default::default()
+                };
+
+                packages.push(package);
+            }
         }
 
         packages.sort();
-        packages
+        Ok(packages)
     }
 }
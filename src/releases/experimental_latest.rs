@@ -1,5 +1,5 @@
 use super::{BuilderBuild, ReleaseType};
-use crate::{package::Package, settings::get_setting};
+use crate::{error::Result, package::Package, settings::get_setting};
 use async_trait::async_trait;
 use derive_deref::{Deref, DerefMut};
 use serde::{Deserialize, Serialize};
@@ -10,8 +10,10 @@ pub struct ExperimentalLatest(Vec<Package>);
 
 #[async_trait]
 impl ReleaseType for ExperimentalLatest {
-    async fn fetch() -> Self {
-        Self(BuilderBuild::ExperimentalLatest.fetch().await)
+    const NAME: &'static str = "Experimental (latest)";
+
+    async fn fetch() -> Result<Self> {
+        Ok(Self(BuilderBuild::ExperimentalLatest.fetch().await?))
     }
 
     fn get_db_path(&self) -> PathBuf {
@@ -1,5 +1,5 @@
 use crate::{
-    package::{Build, Package},
+    package::{Build, Package, PackageState},
     settings::{get_setting, save_settings, set_setting},
 };
 use derive_deref::{Deref, DerefMut};
@@ -34,6 +34,13 @@ impl Installed {
             }
         }
 
+        // Custom packages live wherever the user keeps them, so they're only registered.
+        for package in get_setting().custom_packages.iter() {
+            let mut package = package.clone();
+            package.state = PackageState::Installed;
+            self.push(package);
+        }
+
         self.sort_by_key(|x| x.date);
         self.reverse();
     }
@@ -41,12 +48,19 @@ impl Installed {
     pub fn update_default(&self) {
         if get_setting().use_latest_as_default && get_setting().default_package.is_some() {
             let default_package = get_setting().default_package.clone().unwrap();
+
+            // Custom packages don't get updates, only replaced by hand.
+            if default_package.build == Build::Custom {
+                return;
+            }
+
             // TODO: Fix build comparison.
             // It's comparing Build, which may not be accurate because it may have been filtered
             // and installed with another Build due to BuildType. I could save the BuildType as
             // well and compare that, but it could get out of sync so I'm not sure what to do.
             if let Some(new_default) = self.iter().find(|package| {
                 package.build == default_package.build
+                    && package.target() == default_package.target()
                     && package.version.nth(0).unwrap() == default_package.version.nth(0).unwrap()
                     && package.version.nth(1).unwrap() == default_package.version.nth(1).unwrap()
                     && package.version.nth(2).unwrap() >= default_package.version.nth(2).unwrap()
@@ -1,161 +1,241 @@
 use crate::{
-    helpers::{get_document, get_file_stem},
-    package::{Build, Change, Os, Package},
+    error::{AblavemaError, OrParseError, Result},
+    helpers::{get_document, get_file_stem, site_url},
+    package::{Build, Change, Package, Target},
     releases::{stable_archive::fetch_stable_archive_version, ReleaseType},
     settings::get_setting,
 };
 use async_trait::async_trait;
 use chrono::NaiveDateTime;
 use derive_deref::{Deref, DerefMut};
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use regex::Regex;
 use select::predicate::{Attr, Name};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use versions::Versioning;
 
+/// Path of the page listing every LTS series, which links to a page for each.
+const LTS_INDEX: &str = "download/lts/";
+/// Series that are always fetched, in case the index stops linking to older ones.
+const KNOWN_SERIES: [(u32, u32); 2] = [(2, 83), (2, 93)];
+
+lazy_static! {
+    static ref SERIES_PAGE: Regex = Regex::new(r"/download/lts/(\d+)-(\d+)/?$").unwrap();
+}
+
 #[derive(Clone, Debug, Default, Deref, DerefMut, Deserialize, PartialEq, Serialize)]
 pub struct Lts(Vec<Package>);
 
-#[async_trait]
-impl ReleaseType for Lts {
-    async fn fetch() -> Self {
-        let mut lts = Self::default();
+/// Find the series listed in the LTS index as major and minor version pairs, oldest first.
+async fn discover_series() -> Result<Vec<(u32, u32)>> {
+    let index = site_url("www.blender.org", LTS_INDEX);
+    let document = get_document(&index).await?;
+
+    let discovered: Vec<(u32, u32)> = document
+        .find(Name("a"))
+        .filter_map(|node| {
+            let captures = SERIES_PAGE.captures(node.attr("href")?)?;
+            Some((captures[1].parse().ok()?, captures[2].parse().ok()?))
+        })
+        .collect();
+
+    if discovered.is_empty() {
+        return Err(AblavemaError::parse(&index, "no LTS series listed"));
+    }
 
-        let download_path = "https://ftp.nluug.nl/pub/graphics/blender/release/";
-
-        let lts_info = &[
-            (
-                "https://www.blender.org/download/lts/2-83/",
-                "283",
-                "Blender2.83/",
-            ),
-            (
-                "https://www.blender.org/download/lts/2-93/",
-                "293",
-                "Blender2.93/",
-            ),
-        ];
-
-        let (os, targ_os) = {
-            if cfg!(target_os = "linux") {
-                (Os::Linux, "linux")
-            } else if cfg!(target_os = "windows") {
-                (Os::Windows, "windows")
-            } else if cfg!(target_os = "macos") {
-                (Os::MacOs, "mac")
-            } else {
-                unreachable!("Unexpected OS");
-            }
+    Ok(discovered
+        .into_iter()
+        .chain(KNOWN_SERIES)
+        .sorted()
+        .dedup()
+        .collect())
+}
+
+/// Fetch the releases of a single LTS series from its page, with the dates and checksums of
+/// its directory in the archive.
+async fn fetch_series(major: u32, minor: u32) -> Result<Vec<Package>> {
+    let lts_url = &site_url(
+        "www.blender.org",
+        &format!("{}{}-{}/", LTS_INDEX, major, minor),
+    );
+    // The ID used for the series within its page and its directory in the archive.
+    let lts_ver = format!("{}{}", major, minor);
+    let lts_ver_path = format!("Blender{}.{}/", major, minor);
+
+    let stable_archive_packages = fetch_stable_archive_version(lts_ver_path.clone()).await?;
+    let document = get_document(lts_url).await?;
+    let mut packages = Vec::new();
+
+    for rev in 0.. {
+        let lts_id = format!("lts-release-{}{}", lts_ver, rev);
+        let version = match document.find(Attr("id", lts_id.as_str())).next() {
+            Some(a) => a,
+            None => break,
+        }
+        .text();
+
+        // Releases laid out differently than expected are skipped, so the rest still show up.
+        let version = match version.split_whitespace().nth(2).and_then(Versioning::new) {
+            Some(version) => version,
+            None => continue,
+        };
+
+        let lts_date_id = format!("faq-lts-release-{}{}-1", lts_ver, rev);
+        let section_1 = match document.find(Attr("id", lts_date_id.as_str())).next() {
+            Some(section) => section,
+            None => continue,
         };
 
-        let stable_archive_packages = {
-            let mut packages = Vec::new();
-            for (_, _, version) in lts_info {
-                packages.append(&mut fetch_stable_archive_version(version.to_string()).await);
+        let date = {
+            let text = match section_1.find(Name("p")).next() {
+                Some(paragraph) => paragraph.text(),
+                None => continue,
+            };
+            let mut date = match text.strip_prefix("Released on ") {
+                Some(a) => a,
+                None => continue,
             }
-            packages
+            .trim_end_matches('.')
+            .to_string();
+            date.push_str("-00:00:00");
+            NaiveDateTime::parse_from_str(&date, "%B %d, %Y-%T")
+                .or_parse_error(lts_url, "release with an unexpected date")?
         };
 
-        for (lts_url, lts_ver, lts_ver_path) in lts_info {
-            let document = get_document(lts_url).await;
+        let lts_changelog_id = format!("faq-lts-release-{}{}-2", lts_ver, rev);
+        let section_2 = match document.find(Attr("id", lts_changelog_id.as_str())).next() {
+            Some(section) => section,
+            None => continue,
+        };
 
-            for rev in 0.. {
-                let lts_id = format!("lts-release-{}{}", lts_ver, rev);
-                let version = match document.find(Attr("id", lts_id.as_str())).next() {
-                    Some(a) => a,
-                    None => break,
-                }
-                .text();
-
-                let version = Versioning::new(version.split_whitespace().nth(2).unwrap()).unwrap();
-
-                let lts_date_id = format!("faq-lts-release-{}{}-1", lts_ver, rev);
-                let section_1 = document
-                    .find(Attr("id", lts_date_id.as_str()))
-                    .next()
-                    .unwrap();
-
-                let date = {
-                    let mut date = match section_1
-                        .find(Name("p"))
-                        .next()
-                        .unwrap()
-                        .text()
-                        .strip_prefix("Released on ")
-                    {
-                        Some(a) => a,
-                        None => continue,
-                    }
-                    .strip_suffix('.')
-                    .unwrap()
-                    .to_string();
-                    date.push_str("-00:00:00");
-                    NaiveDateTime::parse_from_str(&date, "%B %d, %Y-%T").unwrap()
+        let changelog = {
+            let mut changelog = Vec::new();
+            for node in section_2.find(Name("li")) {
+                let text = node.text();
+
+                let url = match node.find(Name("a")).next() {
+                    Some(a) => a.attr("href").unwrap_or_default().to_string(),
+                    None => String::new(),
                 };
 
-                let lts_changelog_id = format!("faq-lts-release-{}{}-2", lts_ver, rev);
-                let section_2 = document
-                    .find(Attr("id", lts_changelog_id.as_str()))
-                    .next()
-                    .unwrap();
+                changelog.push(Change { text, url });
+            }
+            changelog
+        };
+
+        for node in section_1.find(Name("a")) {
+            let archive_name = node
+                .attr("href")
+                .and_then(|href| {
+                    href.trim_end_matches("?x69806")
+                        .split_terminator('/')
+                        .last()
+                })
+                .or_parse_error(lts_url, "download without a link")?;
+
+            if archive_name.ends_with(".msi") {
+                continue;
+            }
+            let target = match Target::of_tracked_file(archive_name) {
+                Some(target) => target,
+                None => continue,
+            };
+
+            let url = format!("{}{}", lts_ver_path, archive_name);
+
+            let (date, checksum_url) = {
+                match stable_archive_packages
+                    .iter()
+                    .find(|package| package.url == url)
+                {
+                    Some(package) => (package.date, package.checksum_url.clone()),
+                    None => (date, String::new()),
+                }
+            };
+
+            let package = Package {
+                version: version.clone(),
+                name: get_file_stem(archive_name).to_string(),
+                build: Build::Lts,
+                date,
+                url,
+                checksum_url,
+                os: target.os,
+                arch: target.arch,
+                changelog: changelog.clone(),
+                ..Default::default()
+            };
+
+            packages.push(package);
+        }
+    }
 
-                let changelog = {
-                    let mut changelog = Vec::new();
-                    for node in section_2.find(Name("li")) {
-                        let text = node.text();
+    Ok(packages)
+}
 
-                        let url = match node.find(Name("a")).next() {
-                            Some(a) => a.attr("href").unwrap_or_default().to_string(),
-                            None => String::new(),
-                        };
+impl Lts {
+    /// Gather the changes of every newer point release in each package's LTS series, which is
+    /// what updating from that package to the latest one would bring.
+    pub fn refresh_newer_changes(&mut self) {
+        let series = |version: &Versioning| {
+            let version = version.to_string();
+            match version.rsplit_once('.') {
+                Some((series, _)) => series.to_string(),
+                None => version,
+            }
+        };
 
-                        changelog.push(Change { text, url });
-                    }
+        let releases: Vec<(Versioning, Vec<Change>)> = self
+            .iter()
+            .unique_by(|package| package.version.clone())
+            .map(|package| (package.version.clone(), package.changelog.clone()))
+            .sorted_by(|a, b| b.0.cmp(&a.0))
+            .collect();
+
+        for package in self.iter_mut() {
+            package.newer_changes = releases
+                .iter()
+                .filter(|(version, _)| {
+                    *version > package.version && series(version) == series(&package.version)
+                })
+                .flat_map(|(version, changelog)| {
                     changelog
-                };
+                        .iter()
+                        .map(move |change| (version.clone(), change.clone()))
+                })
+                .collect();
+        }
+    }
+}
 
-                for node in section_1.find(Name("a")) {
-                    let archive_name = node
-                        .attr("href")
-                        .unwrap()
-                        .trim_end_matches("?x69806")
-                        .split_terminator('/')
-                        .last()
-                        .unwrap();
-
-                    if archive_name.ends_with(".msi") || !archive_name.contains(targ_os) {
-                        continue;
-                    }
-
-                    let url = format!("{}{}{}", download_path, lts_ver_path, archive_name);
-
-                    let date = {
-                        match stable_archive_packages
-                            .iter()
-                            .find(|package| package.url == url)
-                        {
-                            Some(package) => package.date,
-                            None => date,
-                        }
-                    };
-
-                    let package = Package {
-                        version: version.clone(),
-                        name: get_file_stem(archive_name).to_string(),
-                        build: Build::Lts,
-                        date,
-                        url,
-                        os,
-                        changelog: changelog.clone(),
-                        ..Default::default()
-                    };
-
-                    lts.push(package);
+#[async_trait]
+impl ReleaseType for Lts {
+    const NAME: &'static str = "Long-term Support";
+
+    async fn fetch() -> Result<Self> {
+        let mut lts = Self::default();
+        let mut errors = Vec::new();
+
+        // A series that fails to load, like a newly listed one laid out differently, is left
+        // out without losing the others.
+        for (major, minor) in discover_series().await? {
+            match fetch_series(major, minor).await {
+                Ok(mut packages) => lts.append(&mut packages),
+                Err(e) => {
+                    eprintln!("Skipping LTS series {}.{}: {}", major, minor, e);
+                    errors.push(e);
                 }
             }
         }
 
+        if lts.is_empty() && !errors.is_empty() {
+            return Err(errors.remove(0));
+        }
+
         lts.sort();
-        lts
+        Ok(lts)
     }
 
     fn get_db_path(&self) -> PathBuf {
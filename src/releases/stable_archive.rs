@@ -1,6 +1,8 @@
 use crate::{
-    helpers::{get_document, get_file_stem},
-    package::{Build, Os, Package},
+    error::{OrParseError, Result},
+    helpers::get_file_stem,
+    mirrors,
+    package::{Build, Package, Target},
     releases::ReleaseType,
     settings::{get_setting, ARCHIVE_DATE_RE},
 };
@@ -18,15 +20,19 @@ pub struct StableArchive(Vec<Package>);
 
 #[async_trait]
 impl ReleaseType for StableArchive {
-    async fn fetch() -> Self {
+    const NAME: &'static str = "Stable (archive)";
+
+    async fn fetch() -> Result<Self> {
         let mut stable_archive = Self::default();
 
         let versions = {
             let mut versions = Vec::new();
-            let document = get_document("https://ftp.nluug.nl/pub/graphics/blender/release/").await;
+            let document = mirrors::get_document("").await?;
 
-            for node in document.find(Name("a")) {
-                let url_path = node.attr("href").unwrap();
+            for url_path in document
+                .find(Name("a"))
+                .filter_map(|node| node.attr("href"))
+            {
                 versions.push(url_path.to_string());
             }
 
@@ -44,11 +50,11 @@ impl ReleaseType for StableArchive {
         }
 
         for handle in handles {
-            stable_archive.append(&mut handle.await.unwrap());
+            stable_archive.append(&mut handle.await.unwrap()?);
         }
 
         stable_archive.sort();
-        stable_archive
+        Ok(stable_archive)
     }
 
     fn get_db_path(&self) -> PathBuf {
@@ -56,33 +62,26 @@ impl ReleaseType for StableArchive {
     }
 }
 
-pub async fn fetch_stable_archive_version(version: String) -> Vec<Package> {
+pub async fn fetch_stable_archive_version(version: String) -> Result<Vec<Package>> {
     let mut packages = Vec::new();
 
-    let url = format!(
-        "{}{}",
-        "https://ftp.nluug.nl/pub/graphics/blender/release/", version
-    );
+    // Relative to the mirror root, so every mirror can be tried when installing.
+    let url = version.clone();
 
-    let document = get_document(url.as_str()).await;
+    let document = mirrors::get_document(&url).await?;
 
-    let version = version.strip_prefix("Blender").unwrap().replace('/', "");
-
-    let (os, targ_os) = {
-        if cfg!(target_os = "linux") {
-            (Os::Linux, "linux")
-        } else if cfg!(target_os = "windows") {
-            (Os::Windows, "win")
-        } else if cfg!(target_os = "macos") {
-            (Os::MacOs, "OS")
-        } else {
-            unreachable!("Unexpected OS");
-        }
-    };
+    let version = version
+        .strip_prefix("Blender")
+        .or_parse_error(&url, "not a release directory")?
+        .replace('/', "");
 
     let mut dates = {
         let mut dates = Vec::new();
-        for node in document.find(Name("pre")).next().unwrap().children() {
+        let listing = document
+            .find(Name("pre"))
+            .next()
+            .or_parse_error(&url, "no directory listing")?;
+        for node in listing.children() {
             if let Some(text) = node.as_text() {
                 if text.chars().filter(|&c| c == '-').count() > 2 {
                     continue;
@@ -99,18 +98,33 @@ pub async fn fetch_stable_archive_version(version: String) -> Vec<Package> {
     let builds = {
         let mut builds = Vec::new();
         for node in document.find(Name("a")) {
-            builds.push(node.attr("href").unwrap());
+            builds.push(
+                node.attr("href")
+                    .or_parse_error(&url, "link without a target")?,
+            );
         }
         builds.retain(|x| !x.ends_with('/') && !x.contains('?'));
         builds.reverse();
         builds
     };
 
+    let checksum_files: Vec<&str> = builds
+        .iter()
+        .filter(|build| build.ends_with(".sha256"))
+        .copied()
+        .collect();
+
     for build in builds {
-        let date = dates.pop().unwrap();
+        let date = dates
+            .pop()
+            .or_parse_error(&url, "fewer dates than builds in the listing")?;
 
-        if !build.contains(targ_os)
-            || build.contains(".msi")
+        let target = match Target::of_tracked_file(build) {
+            Some(target) => target,
+            None => continue,
+        };
+
+        if build.contains(".msi")
             || build.contains(".md")
             || build.contains(".sha256")
             || build.contains(".msix")
@@ -135,11 +149,6 @@ pub async fn fetch_stable_archive_version(version: String) -> Vec<Package> {
             || build.contains("_ppc")
             || build.contains("freebsd")
             || build.contains("FreeBSD")
-        // TODO: Consider disabling old architectures.
-        //|| name.contains("i386")
-        //|| name.contains("i686")
-        //|| name.contains("-win32")
-        //|| name.contains("-windows32")
         {
             continue;
         }
@@ -184,21 +193,38 @@ pub async fn fetch_stable_archive_version(version: String) -> Vec<Package> {
             "2.56beta" => Versioning::new("2.56beta").unwrap(),
             "2.56abeta" => Versioning::new("2.56abeta").unwrap(),
             "2.79latest" => Versioning::new("2.79latest").unwrap(),
-            _ => Versioning::new(build.split_terminator('-').nth(1).unwrap()).unwrap(),
+            _ => build
+                .split_terminator('-')
+                .nth(1)
+                .and_then(Versioning::new)
+                .or_parse_error(&url, "build without a version in its name")?,
+        };
+
+        // Checksums are either published per archive or in one file per release,
+        // such as "blender-3.6.5.sha256" covering every archive of 3.6.5.
+        let checksum_url = match checksum_files.iter().find(|checksum_file| {
+            **checksum_file == format!("{}.sha256", build)
+                || build.starts_with(&format!("{}-", checksum_file.trim_end_matches(".sha256")))
+        }) {
+            Some(checksum_file) => format!("{}{}", url, checksum_file),
+            None => String::new(),
         };
 
         let package = Package {
             version,
             name: get_file_stem(build).to_string(),
             build: Build::StableArchive,
-            date: NaiveDateTime::parse_from_str(&date, "%d-%b-%Y %T").unwrap(),
+            date: NaiveDateTime::parse_from_str(&date, "%d-%b-%Y %T")
+                .or_parse_error(&url, "build with an unexpected date")?,
             url: format!("{}{}", url, build),
-            os,
+            checksum_url,
+            os: target.os,
+            arch: target.arch,
             ..Default::default()
         };
 
         packages.push(package);
     }
 
-    packages
+    Ok(packages)
 }
@@ -1,13 +1,17 @@
 use crate::{
-    helpers::{get_document, get_file_stem},
-    package::{Build, Os, Package},
+    error::{AblavemaError, OrParseError, Result},
+    helpers::{get_document, get_file_stem, site_url},
+    package::{Arch, Build, Os, Package, Target},
     releases::{stable_archive::fetch_stable_archive_version, ReleaseType},
     settings::get_setting,
 };
 use async_trait::async_trait;
 use chrono::NaiveDateTime;
 use derive_deref::{Deref, DerefMut};
-use select::predicate::{Attr, Class, Name};
+use select::{
+    document::Document,
+    predicate::{Attr, Class, Name},
+};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use versions::Versioning;
@@ -17,91 +21,132 @@ pub struct StableLatest(Vec<Package>);
 
 #[async_trait]
 impl ReleaseType for StableLatest {
-    async fn fetch() -> Self {
-        let (mut package, version_path) = {
-            let url = "https://www.blender.org/download/";
-            let document = get_document(url).await;
-
-            let (os, targ_os) = {
-                if cfg!(target_os = "linux") {
-                    (Os::Linux, "linux")
-                } else if cfg!(target_os = "windows") {
-                    (Os::Windows, "windows")
-                } else if cfg!(target_os = "macos") {
-                    (Os::MacOs, "macos")
-                } else {
-                    unreachable!("Unexpected OS");
-                }
-            };
+    const NAME: &'static str = "Stable (latest)";
+
+    async fn fetch() -> Result<Self> {
+        let download_page = &site_url("www.blender.org", "download/");
+        let targets = get_setting().targets.clone();
+
+        let (mut packages, version_paths) = {
+            let document = get_document(download_page).await?;
+            read_download_page(&document, download_page, &targets)?
+        };
+
+        let mut stable_archive_packages = Vec::new();
+        for version_path in version_paths {
+            stable_archive_packages.append(&mut fetch_stable_archive_version(version_path).await?);
+        }
+
+        for package in &mut packages {
+            if let Some(a_package) = stable_archive_packages
+                .iter()
+                .find(|a_package| a_package.url == package.url)
+            {
+                package.date = a_package.date;
+                package.checksum_url = a_package.checksum_url.clone();
+            }
+        }
+
+        Ok(Self(packages))
+    }
+
+    fn get_db_path(&self) -> PathBuf {
+        get_setting().databases_dir.join("stable_latest.ron")
+    }
+}
+
+/// Read the packages offered by the download page for the targets, along with the directories
+/// of the release archive they're in. Links that don't lead to the release directory, like
+/// other options added to the page, are skipped.
+pub(super) fn read_download_page(
+    document: &Document,
+    download_page: &str,
+    targets: &[Target],
+) -> Result<(Vec<Package>, Vec<String>)> {
+    let mut packages = Vec::new();
+    let mut version_paths = Vec::new();
+
+    for os in Os::ALL
+        .into_iter()
+        .filter(|os| targets.iter().any(|target| target.os == *os))
+    {
+        let id = match os {
+            Os::Linux => "linux",
+            Os::Windows => "windows",
+            Os::MacOs => "macos",
+        };
 
-            let node = document.find(Attr("id", targ_os)).next().unwrap();
+        let node = document
+            .find(Attr("id", id))
+            .next()
+            .or_parse_error(download_page, &format!("no download for {}", os))?;
 
-            let (version_path, file_name) = node
-                .find(Name("a"))
-                .next()
-                .unwrap()
+        // Only needed once there's a package, since the installers don't show it.
+        let date = node
+            .find(Class("dl-build-details-popup"))
+            .next()
+            .and_then(|popup| popup.find(Name("small")).next())
+            .map(|small| small.text())
+            .and_then(|details| {
+                let date = details.get(details.find("on")? + 3..)?;
+                let (date, _) = date.split_once(" ·")?;
+                NaiveDateTime::parse_from_str(&format!("{}-00:00:00", date), "%B %d, %Y-%T").ok()
+            });
+
+        // Every architecture of an OS is offered from the same place, the installers aside.
+        for link in node.find(Name("a")) {
+            let (version_path, file_name) = match link
                 .attr("href")
-                .unwrap()
-                .strip_prefix(&url)
-                .unwrap()
-                .strip_prefix("release/")
-                .unwrap()
-                .split_once('/')
-                .unwrap();
-
-            let version = {
-                let mut version = node.find(Name("a")).next().unwrap().text();
-                version.retain(|c| c.is_numeric() || c.is_ascii_punctuation());
-                Versioning::new(&version).unwrap()
+                .and_then(|href| href.strip_prefix(download_page)?.strip_prefix("release/"))
+                .and_then(|path| path.split_once('/'))
+            {
+                Some(paths) => paths,
+                None => continue,
             };
+            let file_name = file_name.trim_end_matches('/');
 
-            let url = format!(
-                "https://ftp.nluug.nl/pub/graphics/blender/release/{}/{}",
-                version_path,
-                file_name.strip_suffix('/').unwrap()
-            );
-
-            let date = {
-                let mut date = node
-                    .find(Class("dl-build-details-popup"))
-                    .next()
-                    .unwrap()
-                    .find(Name("small"))
-                    .nth(0)
-                    .unwrap()
-                    .text();
-                let mut date = date.split_off(date.find("on").unwrap() + 3);
-                date.truncate(date.find(" ·").unwrap());
-                date.push_str("-00:00:00");
-                NaiveDateTime::parse_from_str(&date, "%B %d, %Y-%T").unwrap()
-            };
+            if file_name.ends_with(".msi") || file_name.ends_with(".msix") {
+                continue;
+            }
+            let arch = Arch::from_file_name(file_name);
+            if !targets.contains(&Target { os, arch }) {
+                continue;
+            }
+
+            // Like "Download Blender 4.1.1" or "Blender 4.1.1 Arm64 Portable".
+            let version = link
+                .text()
+                .split_whitespace()
+                .find(|word| word.starts_with(|c: char| c.is_ascii_digit()))
+                .and_then(Versioning::new)
+                .or_parse_error(download_page, "download without a version")?;
+
+            let url = format!("{}/{}", version_path, file_name);
 
-            let package = Package {
+            packages.push(Package {
                 version,
                 name: get_file_stem(&url).to_string(),
                 build: Build::StableLatest,
-                date,
+                date: date.or_parse_error(download_page, "download without a release date")?,
                 url,
                 os,
+                arch,
                 ..Default::default()
-            };
-
-            (package, version_path.to_string())
-        };
+            });
 
-        let stable_archive_packages =
-            fetch_stable_archive_version(format!("{}/", version_path)).await;
-        if let Some(a_package) = stable_archive_packages
-            .iter()
-            .find(|a_package| a_package.url == package.url)
-        {
-            package.date = a_package.date;
+            let version_path = format!("{}/", version_path);
+            if !version_paths.contains(&version_path) {
+                version_paths.push(version_path);
+            }
         }
-
-        Self(vec![package])
     }
 
-    fn get_db_path(&self) -> PathBuf {
-        get_setting().databases_dir.join("stable_latest.ron")
+    if packages.is_empty() {
+        return Err(AblavemaError::parse(
+            download_page,
+            "no downloads in the release directory",
+        ));
     }
+
+    Ok((packages, version_paths))
 }
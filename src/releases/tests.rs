@@ -0,0 +1,714 @@
+//! Runs every scraper against recorded pages from `tests/fixtures`, which are served by a
+//! stand-in for Blender's sites so a change in how a page is read shows up as a failing test
+//! instead of as missing packages.
+
+use super::*;
+use crate::{
+    package::{Arch, Change, Target},
+    settings::{BASE_URL_ENV, CONFIG_FILE_ENV},
+    test_helpers::scratch_dir,
+};
+use chrono::NaiveDate;
+use lazy_static::lazy_static;
+use select::document::Document;
+use std::{
+    env::set_var,
+    fs::read,
+    io::{BufRead, BufReader},
+    net::{TcpListener, TcpStream},
+    path::Path,
+    thread,
+};
+
+/// Sites that have recorded pages, which are also the links rewritten to point at the stand-in.
+const HOSTS: [&str; 3] = [
+    "builder.blender.org",
+    "download.blender.org",
+    "www.blender.org",
+];
+
+/// Targets the recorded pages are fetched for, which leave out what's built for Windows on Arm
+/// and macOS to check that untracked targets are skipped.
+const LINUX: Target = Target {
+    os: Os::Linux,
+    arch: Arch::X86_64,
+};
+const LINUX_ARM: Target = Target {
+    os: Os::Linux,
+    arch: Arch::Arm64,
+};
+const WINDOWS: Target = Target {
+    os: Os::Windows,
+    arch: Arch::X86_64,
+};
+
+lazy_static! {
+    /// Root of the stand-in, which is started once and shared by every test.
+    static ref BASE_URL: String = {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        let server_base_url = base_url.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let base_url = server_base_url.clone();
+                thread::spawn(move || serve(stream, &base_url));
+            }
+        });
+
+        let config_dir = scratch_dir("fixtures");
+        let config = config_dir.join("config.ron");
+        std::fs::write(
+            &config,
+            format!(
+                "(packages_dir: {:?}, databases_dir: {:?}, cache_dir: {:?}, \
+                 mirrors: [(url: \"{}/download.blender.org/release/\")], \
+                 targets: [(os: Linux, arch: X86_64), (os: Linux, arch: Arm64), \
+                 (os: Windows, arch: X86_64)])",
+                config_dir.join("packages"),
+                config_dir.join("databases"),
+                config_dir.join("cache"),
+                base_url,
+            ),
+        )
+        .unwrap();
+
+        set_var(CONFIG_FILE_ENV, &config);
+        set_var(BASE_URL_ENV, &base_url);
+        init_settings();
+
+        base_url
+    };
+}
+
+/// Answer a single request with the recorded page at its path, or with a 404 if there's none.
+fn serve(mut stream: TcpStream, base_url: &str) {
+    let mut request_line = String::new();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    loop {
+        let mut header = String::new();
+        match reader.read_line(&mut header) {
+            Ok(0) | Err(_) => break,
+            Ok(_) if header.trim().is_empty() => break,
+            Ok(_) => (),
+        }
+    }
+
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or_default()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches('/');
+    let mut file = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(path);
+    if path.is_empty() || path.ends_with('/') {
+        file.push("index.html");
+    }
+
+    // Connections are closed after every response, since each test has its own runtime and
+    // a pooled connection from one that's gone can't be used by the next.
+    let response = match read(&file) {
+        Ok(page) if !path.contains("..") => {
+            let mut page = String::from_utf8(page).unwrap();
+            for host in HOSTS {
+                page = page.replace(
+                    &format!("https://{}/", host),
+                    &format!("{}/{}/", base_url, host),
+                );
+            }
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                page.len(),
+                page
+            )
+        }
+        _ => {
+            String::from("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+        }
+    };
+    let _ = stream.write_all(response.as_bytes());
+}
+
+fn setup() -> &'static str {
+    &BASE_URL
+}
+
+/// A date from the listings, which are in UTC.
+fn date(year: i32, month: u32, day: u32, time: (u32, u32, u32)) -> NaiveDateTime {
+    NaiveDate::from_ymd(year, month, day).and_hms(time.0, time.1, time.2)
+}
+
+/// Builds only show the day they were made on, so they're assumed to be from this year.
+fn this_year(month: u32, day: u32, time: (u32, u32, u32)) -> NaiveDateTime {
+    date(Utc::today().year(), month, day, time)
+}
+
+/// Packages are compared in full, since `PartialEq` only looks at their name and version.
+fn assert_packages(actual: &[Package], expected: &[Package]) {
+    let (actual, expected) = (format!("{:#?}", actual), format!("{:#?}", expected));
+    assert!(
+        actual == expected,
+        "fetched packages differ\nfetched: {}\nexpected: {}",
+        actual,
+        expected
+    );
+}
+
+fn builder_package(
+    target: Target,
+    directory: &str,
+    name: &str,
+    build: Build,
+    version: &str,
+    date: NaiveDateTime,
+    commit: &str,
+) -> Package {
+    let url = format!(
+        "{}/builder.blender.org/download/{}{}.{}",
+        setup(),
+        directory,
+        name,
+        if target.os == Os::Windows {
+            "zip"
+        } else {
+            "tar.xz"
+        }
+    );
+    Package {
+        version: Versioning::new(version).unwrap(),
+        name: name.to_string(),
+        build,
+        date,
+        commit: commit.to_string(),
+        checksum_url: format!("{}.sha256", url),
+        url,
+        os: target.os,
+        arch: target.arch,
+        ..Default::default()
+    }
+}
+
+fn archive_package(
+    target: Target,
+    build: Build,
+    directory: &str,
+    file: &str,
+    version: &str,
+    date: NaiveDateTime,
+    checksum_file: &str,
+) -> Package {
+    Package {
+        version: Versioning::new(version).unwrap(),
+        name: get_file_stem(file).to_string(),
+        build,
+        date,
+        url: format!("{}{}", directory, file),
+        checksum_url: if checksum_file.is_empty() {
+            String::new()
+        } else {
+            format!("{}{}", directory, checksum_file)
+        },
+        os: target.os,
+        arch: target.arch,
+        ..Default::default()
+    }
+}
+
+fn change(text: &str, issue: &str) -> Change {
+    Change {
+        text: text.to_string(),
+        url: if issue.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "https://projects.blender.org/blender/blender/issues/{}",
+                issue
+            )
+        },
+    }
+}
+
+#[tokio::test]
+async fn daily_latest() {
+    setup();
+    assert_packages(
+        &DailyLatest::fetch().await.unwrap(),
+        &[
+            builder_package(
+                WINDOWS,
+                "daily/",
+                "blender-4.3.0-alpha+main.5e1c2fdba6a7-windows.amd64-release",
+                Build::DailyLatest(String::from("Alpha")),
+                "4.3.0",
+                this_year(7, 16, (10, 52, 40)),
+                "5e1c2fdba6a7",
+            ),
+            Package {
+                checksum_url: String::new(),
+                ..builder_package(
+                    LINUX_ARM,
+                    "daily/",
+                    "blender-4.3.0-alpha+main.5e1c2fdba6a7-linux.arm64-release",
+                    Build::DailyLatest(String::from("Alpha")),
+                    "4.3.0",
+                    this_year(7, 16, (10, 49, 55)),
+                    "5e1c2fdba6a7",
+                )
+            },
+            builder_package(
+                LINUX,
+                "daily/",
+                "blender-4.3.0-alpha+main.5e1c2fdba6a7-linux.x86_64-release",
+                Build::DailyLatest(String::from("Alpha")),
+                "4.3.0",
+                this_year(7, 16, (10, 44, 7)),
+                "5e1c2fdba6a7",
+            ),
+            builder_package(
+                LINUX,
+                "daily/",
+                "blender-4.2.1-candidate+v42.9b7e0ee4b56c-linux.x86_64-release",
+                Build::DailyLatest(String::from("Candidate")),
+                "4.2.1",
+                this_year(7, 15, (22, 10, 31)),
+                "9b7e0ee4b56c",
+            ),
+        ],
+    );
+}
+
+#[tokio::test]
+async fn daily_archive() {
+    setup();
+    assert_packages(
+        &DailyArchive::fetch().await.unwrap(),
+        &[
+            builder_package(
+                LINUX,
+                "daily/archive/",
+                "blender-4.3.0-alpha+main.0ee6bff9d3d1-linux.x86_64-release",
+                Build::DailyArchive(String::from("Alpha")),
+                "4.3.0",
+                this_year(7, 14, (9, 12, 3)),
+                "0ee6bff9d3d1",
+            ),
+            builder_package(
+                LINUX,
+                "daily/archive/",
+                "blender-4.3.0-alpha+main.61ddf9f0a0e2-linux.x86_64-release",
+                Build::DailyArchive(String::from("Alpha")),
+                "4.3.0",
+                this_year(7, 13, (8, 55, 47)),
+                "61ddf9f0a0e2",
+            ),
+        ],
+    );
+}
+
+#[tokio::test]
+async fn experimental_latest() {
+    setup();
+    assert_packages(
+        &ExperimentalLatest::fetch().await.unwrap(),
+        &[
+            builder_package(
+                WINDOWS,
+                "experimental/",
+                "blender-4.3.0-alpha+npr-prototype.2b2b7dd9a1f3-windows.amd64-release",
+                Build::ExperimentalLatest(String::from("npr-prototype")),
+                "4.3.0",
+                this_year(7, 12, (17, 41, 12)),
+                "2b2b7dd9a1f3",
+            ),
+            builder_package(
+                LINUX,
+                "experimental/",
+                "blender-4.3.0-alpha+npr-prototype.2b2b7dd9a1f3-linux.x86_64-release",
+                Build::ExperimentalLatest(String::from("npr-prototype")),
+                "4.3.0",
+                this_year(7, 12, (17, 30, 0)),
+                "2b2b7dd9a1f3",
+            ),
+        ],
+    );
+}
+
+#[tokio::test]
+async fn experimental_archive() {
+    setup();
+    assert_packages(
+        &ExperimentalArchive::fetch().await.unwrap(),
+        &[builder_package(
+            LINUX,
+            "experimental/archive/",
+            "blender-4.2.0-alpha+cycles-light-linking.c1c5a9e2e3d0-linux.x86_64-release",
+            Build::ExperimentalArchive(String::from("cycles-light-linking")),
+            "4.2.0",
+            this_year(5, 2, (11, 3, 27)),
+            "c1c5a9e2e3d0",
+        )],
+    );
+}
+
+#[tokio::test]
+async fn patch_latest() {
+    setup();
+    assert_packages(
+        &PatchLatest::fetch().await.unwrap(),
+        &[builder_package(
+            LINUX,
+            "patch/",
+            "blender-4.3.0-alpha+PR123456.8f0d1e7c5b44-linux.x86_64-release",
+            Build::PatchLatest(String::from("PR123456")),
+            "4.3.0",
+            this_year(7, 11, (14, 20, 9)),
+            "8f0d1e7c5b44",
+        )],
+    );
+}
+
+/// The recorded page has no builds for Windows at all, which only leaves them out.
+#[tokio::test]
+async fn patch_archive() {
+    setup();
+    assert_packages(
+        &PatchArchive::fetch().await.unwrap(),
+        &[builder_package(
+            LINUX,
+            "patch/archive/",
+            "blender-4.2.0-alpha+PR118000.3a4f6c2d9e81-linux.x86_64-release",
+            Build::PatchArchive(String::from("PR118000")),
+            "4.2.0",
+            this_year(4, 20, (6, 45, 52)),
+            "3a4f6c2d9e81",
+        )],
+    );
+}
+
+#[tokio::test]
+async fn stable_latest() {
+    setup();
+    assert_packages(
+        &StableLatest::fetch().await.unwrap(),
+        &[archive_package(
+            LINUX,
+            Build::StableLatest,
+            "Blender4.1/",
+            "blender-4.1.1-linux-x64.tar.xz",
+            "4.1.1",
+            date(2024, 4, 16, (10, 57, 0)),
+            "blender-4.1.1.sha256",
+        )],
+    );
+}
+
+/// The download page as it's laid out since it started listing portable and Arm builds, along
+/// with links elsewhere, under the installer of each OS.
+#[test]
+fn stable_latest_other_options() {
+    let download_page = "https://www.blender.org/download/";
+    let page = read(
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/www.blender.org/download/other-options.html"),
+    )
+    .unwrap();
+    let document = Document::from_read(&page[..]).unwrap();
+
+    let (packages, version_paths) =
+        stable_latest::read_download_page(&document, download_page, &[LINUX, LINUX_ARM, WINDOWS])
+            .unwrap();
+    assert_packages(
+        &packages,
+        &[
+            archive_package(
+                LINUX,
+                Build::StableLatest,
+                "Blender4.1/",
+                "blender-4.1.1-linux-x64.tar.xz",
+                "4.1.1",
+                date(2024, 4, 16, (0, 0, 0)),
+                "",
+            ),
+            archive_package(
+                WINDOWS,
+                Build::StableLatest,
+                "Blender4.1/",
+                "blender-4.1.1-windows-x64.zip",
+                "4.1.1",
+                date(2024, 4, 16, (0, 0, 0)),
+                "",
+            ),
+        ],
+    );
+    assert_eq!(version_paths, [String::from("Blender4.1/")]);
+
+    assert!(stable_latest::read_download_page(&document, download_page, &[LINUX_ARM]).is_err());
+}
+
+#[tokio::test]
+async fn stable_archive() {
+    setup();
+    assert_packages(
+        &StableArchive::fetch().await.unwrap(),
+        &[
+            archive_package(
+                WINDOWS,
+                Build::StableArchive,
+                "Blender4.1/",
+                "blender-4.1.1-windows-x64.zip",
+                "4.1.1",
+                date(2024, 4, 16, (10, 59, 0)),
+                "blender-4.1.1.sha256",
+            ),
+            archive_package(
+                LINUX,
+                Build::StableArchive,
+                "Blender4.1/",
+                "blender-4.1.1-linux-x64.tar.xz",
+                "4.1.1",
+                date(2024, 4, 16, (10, 57, 0)),
+                "blender-4.1.1.sha256",
+            ),
+            archive_package(
+                WINDOWS,
+                Build::StableArchive,
+                "Blender3.6/",
+                "blender-3.6.1-windows-x64.zip",
+                "3.6.1",
+                date(2023, 7, 17, (18, 23, 0)),
+                "blender-3.6.1.sha256",
+            ),
+            archive_package(
+                LINUX,
+                Build::StableArchive,
+                "Blender3.6/",
+                "blender-3.6.1-linux-x64.tar.xz",
+                "3.6.1",
+                date(2023, 7, 17, (18, 21, 0)),
+                "blender-3.6.1.sha256",
+            ),
+            archive_package(
+                WINDOWS,
+                Build::StableArchive,
+                "Blender3.6/",
+                "blender-3.6.0-windows-x64.zip",
+                "3.6.0",
+                date(2023, 6, 27, (9, 56, 0)),
+                "blender-3.6.0.sha256",
+            ),
+            archive_package(
+                LINUX,
+                Build::StableArchive,
+                "Blender3.6/",
+                "blender-3.6.0-linux-x64.tar.xz",
+                "3.6.0",
+                date(2023, 6, 27, (9, 54, 0)),
+                "blender-3.6.0.sha256",
+            ),
+            archive_package(
+                WINDOWS,
+                Build::StableArchive,
+                "Blender2.93/",
+                "blender-2.93.18-windows-x64.zip",
+                "2.93.18",
+                date(2024, 6, 5, (10, 30, 0)),
+                "",
+            ),
+            archive_package(
+                LINUX,
+                Build::StableArchive,
+                "Blender2.93/",
+                "blender-2.93.18-linux-x64.tar.xz",
+                "2.93.18",
+                date(2024, 6, 5, (10, 28, 0)),
+                "blender-2.93.18-linux-x64.tar.xz.sha256",
+            ),
+            archive_package(
+                WINDOWS,
+                Build::StableArchive,
+                "Blender2.83/",
+                "blender-2.83.20-windows-x64.zip",
+                "2.83.20",
+                date(2024, 1, 25, (12, 0, 0)),
+                "blender-2.83.20.sha256",
+            ),
+            archive_package(
+                LINUX,
+                Build::StableArchive,
+                "Blender2.83/",
+                "blender-2.83.20-linux-x64.tar.xz",
+                "2.83.20",
+                date(2024, 1, 25, (11, 58, 0)),
+                "blender-2.83.20.sha256",
+            ),
+            archive_package(
+                WINDOWS,
+                Build::StableArchive,
+                "Blender2.79/",
+                "blender-2.79b-windows64.zip",
+                "2.79b",
+                date(2018, 3, 22, (14, 11, 0)),
+                "",
+            ),
+            archive_package(
+                LINUX,
+                Build::StableArchive,
+                "Blender2.79/",
+                "blender-2.79b-linux-glibc219-x86_64.tar.bz2",
+                "2.79b",
+                date(2018, 3, 22, (14, 10, 0)),
+                "",
+            ),
+            archive_package(
+                LINUX,
+                Build::StableArchive,
+                "Blender2.79/latest/",
+                "blender-2.79-e045fe53f1b0-linux-glibc224-x86_64.tar.bz2",
+                "2.79latest",
+                date(2018, 10, 12, (17, 28, 0)),
+                "",
+            ),
+        ],
+    );
+}
+
+/// The index also links to 4.2, which has no recorded pages and is left out, and 3.6.2 has no
+/// downloads yet, so only that release is left out of its series.
+#[tokio::test]
+async fn lts() {
+    setup();
+    let lts_package = |target, directory, file, version, date, checksum_file, changelog| Package {
+        changelog,
+        ..archive_package(
+            target,
+            Build::Lts,
+            directory,
+            file,
+            version,
+            date,
+            checksum_file,
+        )
+    };
+    assert_packages(
+        &Lts::fetch().await.unwrap(),
+        &[
+            lts_package(
+                LINUX,
+                "Blender3.6/",
+                "blender-3.6.1-linux-x64.tar.xz",
+                "3.6.1",
+                date(2023, 7, 17, (18, 21, 0)),
+                "blender-3.6.1.sha256",
+                vec![
+                    change(
+                        "Fix UV unwrapping producing overlapping islands. #109855",
+                        "109855",
+                    ),
+                    change("Fix Cycles crash with empty light trees. #110121", "110121"),
+                ],
+            ),
+            lts_package(
+                WINDOWS,
+                "Blender3.6/",
+                "blender-3.6.1-windows-x64.zip",
+                "3.6.1",
+                date(2023, 7, 17, (18, 23, 0)),
+                "blender-3.6.1.sha256",
+                vec![
+                    change(
+                        "Fix UV unwrapping producing overlapping islands. #109855",
+                        "109855",
+                    ),
+                    change("Fix Cycles crash with empty light trees. #110121", "110121"),
+                ],
+            ),
+            lts_package(
+                LINUX,
+                "Blender3.6/",
+                "blender-3.6.0-linux-x64.tar.xz",
+                "3.6.0",
+                date(2023, 6, 27, (9, 54, 0)),
+                "blender-3.6.0.sha256",
+                Vec::new(),
+            ),
+            lts_package(
+                WINDOWS,
+                "Blender3.6/",
+                "blender-3.6.0-windows-x64.zip",
+                "3.6.0",
+                date(2023, 6, 27, (9, 56, 0)),
+                "blender-3.6.0.sha256",
+                Vec::new(),
+            ),
+            lts_package(
+                LINUX,
+                "Blender2.93/",
+                "blender-2.93.18-linux-x64.tar.xz",
+                "2.93.18",
+                date(2024, 6, 5, (10, 28, 0)),
+                "blender-2.93.18-linux-x64.tar.xz.sha256",
+                vec![change(
+                    "Security fix for Python scripts in linked files.",
+                    "",
+                )],
+            ),
+            lts_package(
+                WINDOWS,
+                "Blender2.93/",
+                "blender-2.93.18-windows-x64.zip",
+                "2.93.18",
+                date(2024, 6, 5, (10, 30, 0)),
+                "",
+                vec![change(
+                    "Security fix for Python scripts in linked files.",
+                    "",
+                )],
+            ),
+            lts_package(
+                LINUX,
+                "Blender2.83/",
+                "blender-2.83.20-linux-x64.tar.xz",
+                "2.83.20",
+                date(2024, 1, 25, (11, 58, 0)),
+                "blender-2.83.20.sha256",
+                vec![change(
+                    "Fix crash when opening files with missing libraries. #116788",
+                    "116788",
+                )],
+            ),
+            lts_package(
+                WINDOWS,
+                "Blender2.83/",
+                "blender-2.83.20-windows-x64.zip",
+                "2.83.20",
+                date(2024, 1, 25, (12, 0, 0)),
+                "blender-2.83.20.sha256",
+                vec![change(
+                    "Fix crash when opening files with missing libraries. #116788",
+                    "116788",
+                )],
+            ),
+        ],
+    );
+}
+
+#[tokio::test]
+async fn missing_page() {
+    let base_url = setup();
+    let error = get_document(&format!("{}/www.blender.org/nowhere/", base_url))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(error, AblavemaError::Network { .. }),
+        "{:?}",
+        error
+    );
+}
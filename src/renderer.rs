@@ -0,0 +1,196 @@
+use crate::helpers::{blender_command, send_or_return};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{fmt, path::PathBuf, process::Stdio};
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::Command,
+    sync::mpsc::UnboundedSender,
+};
+
+lazy_static! {
+    static ref FRAME: Regex = Regex::new(r"^Fra:(\d+) ").unwrap();
+    static ref REMAINING: Regex = Regex::new(r"\| Remaining:([\d:.]+)").unwrap();
+    static ref SAMPLES: Regex =
+        Regex::new(r"Sample (\d+)/(\d+)|Rendering (\d+) / (\d+) samples").unwrap();
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Frames {
+    /// Whatever frame range is set in the file itself.
+    All,
+    Range {
+        start: u32,
+        end: u32,
+    },
+}
+
+impl Frames {
+    /// Frames from what was typed in for the start and end, where leaving both empty means all
+    /// of them and leaving one empty means a single frame.
+    pub fn from_input(start: &str, end: &str) -> Result<Frames, String> {
+        let parse = |frame: &str| {
+            frame
+                .parse::<u32>()
+                .map_err(|_| format!("'{}' isn't a frame number", frame))
+        };
+
+        match (start.trim(), end.trim()) {
+            ("", "") => Ok(Frames::All),
+            (frame, "") | ("", frame) => {
+                let frame = parse(frame)?;
+                Ok(Frames::Range {
+                    start: frame,
+                    end: frame,
+                })
+            }
+            (start, end) => {
+                let (start, end) = (parse(start)?, parse(end)?);
+                if start > end {
+                    Err(String::from(
+                        "the start frame can't come after the end frame",
+                    ))
+                } else {
+                    Ok(Frames::Range { start, end })
+                }
+            }
+        }
+    }
+
+    pub fn count(&self) -> Option<u32> {
+        match self {
+            Frames::All => None,
+            Frames::Range { start, end } => Some(end - start + 1),
+        }
+    }
+}
+
+impl fmt::Display for Frames {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Frames::All => write!(f, "all"),
+            Frames::Range { start, end } if start == end => write!(f, "{}", start),
+            Frames::Range { start, end } => write!(f, "{} to {}", start, end),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum JobState {
+    Queued,
+    Finished,
+    Failed(String),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RenderJob {
+    pub id: u64,
+    pub file: PathBuf,
+    /// Name of the package the file gets rendered with.
+    pub package: String,
+    pub frames: Frames,
+    pub state: JobState,
+}
+
+/// What Blender last reported about the frame it's rendering.
+#[derive(Clone, Debug, Default)]
+pub struct RenderStatus {
+    pub frame: u32,
+    pub samples: Option<(u32, u32)>,
+    pub remaining: Option<String>,
+}
+
+impl RenderStatus {
+    fn parse(line: &str) -> Option<RenderStatus> {
+        let frame = FRAME.captures(line)?[1].parse().ok()?;
+        let samples = SAMPLES.captures(line).and_then(|captures| {
+            let done = captures.get(1).or_else(|| captures.get(3))?;
+            let total = captures.get(2).or_else(|| captures.get(4))?;
+            Some((done.as_str().parse().ok()?, total.as_str().parse().ok()?))
+        });
+        let remaining = REMAINING
+            .captures(line)
+            .map(|captures| captures[1].to_string());
+
+        Some(RenderStatus {
+            frame,
+            samples,
+            remaining,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Progress {
+    Started,
+    Status(RenderStatus),
+    FrameSaved,
+    Finished,
+    Errored(String),
+}
+
+/// Render the job in the background with its package, reporting what Blender prints as it goes.
+/// Blender gets killed if the receiver is dropped.
+pub async fn render(job: RenderJob, sender: UnboundedSender<Progress>) {
+    let mut cmd = Command::from(blender_command(&job.package, Some(&job.file)));
+    cmd.arg("-b").arg(&job.file);
+    match job.frames {
+        Frames::All => {
+            cmd.arg("-a");
+        }
+        Frames::Range { start, end } if start == end => {
+            cmd.arg("-f").arg(start.to_string());
+        }
+        Frames::Range { start, end } => {
+            cmd.args(["-s", &start.to_string(), "-e", &end.to_string(), "-a"]);
+        }
+    }
+    cmd.stdout(Stdio::piped()).kill_on_drop(true);
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            let _ = sender.send(Progress::Errored(format!(
+                "couldn't start '{}': {}",
+                job.package, e
+            )));
+            return;
+        }
+    };
+    send_or_return!(sender, Progress::Started);
+
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+    let mut last_error = None;
+    let mut frames_saved = 0;
+
+    loop {
+        let line = tokio::select! {
+            line = lines.next_line() => line,
+            _ = sender.closed() => return,
+        };
+        let line = match line {
+            Ok(Some(line)) => line,
+            _ => break,
+        };
+
+        if let Some(status) = RenderStatus::parse(&line) {
+            send_or_return!(sender, Progress::Status(status));
+        } else if line.starts_with("Saved: ") || line.starts_with("Append frame ") {
+            frames_saved += 1;
+            send_or_return!(sender, Progress::FrameSaved);
+        } else if let Some(error) = line.strip_prefix("Error: ") {
+            last_error = Some(error.to_string());
+        }
+    }
+
+    let _ = sender.send(match child.wait().await {
+        Ok(status) if status.success() && (frames_saved > 0 || last_error.is_none()) => {
+            Progress::Finished
+        }
+        Ok(status) => Progress::Errored(
+            last_error.unwrap_or_else(|| format!("Blender exited with {}", status)),
+        ),
+        Err(e) => Progress::Errored(e.to_string()),
+    });
+}
@@ -1,11 +1,15 @@
 use crate::{
+    association::AssociationRule,
     gui::{
         filters::Filters,
         sort_by::SortBy,
         style::Theme,
         tabs::{recent_files::RecentFiles, Tab},
     },
-    package::Package,
+    installer::QueuedDownload,
+    mirrors::Mirror,
+    package::{Package, Target},
+    renderer::RenderJob,
 };
 use derive_deref::{Deref, DerefMut};
 use device_query::Keycode;
@@ -18,6 +22,7 @@ use ron::{
 };
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     env::current_exe,
     env::var,
     fs::{create_dir_all, read_to_string, File},
@@ -48,6 +53,7 @@ pub fn set_setting() -> RwLockWriteGuard<'static, Settings> {
 
 const CONFIG_NAME: &str = "config.ron";
 pub const CONFIG_FILE_ENV: &str = "ABLAVEMA_CONFIG_FILE";
+pub const BASE_URL_ENV: &str = "ABLAVEMA_BASE_URL";
 pub static PORTABLE: AtomicBool = AtomicBool::new(false);
 pub static CAN_CONNECT: AtomicBool = AtomicBool::new(true);
 pub static LAUNCH_GUI: AtomicBool = AtomicBool::new(false);
@@ -86,9 +92,31 @@ pub struct Settings {
     pub bookmarks: Bookmarks,
     pub tab: Tab,
     pub default_package: Option<Package>,
+    pub custom_packages: Vec<Package>,
+    /// Name of the profile every package is launched with, if it's not Blender's default.
+    pub package_profiles: HashMap<String, String>,
+    /// Packages and profiles to open files with depending on their path, where the first
+    /// matching rule is used, see `association::matching_rule`.
+    pub association_rules: Vec<AssociationRule>,
+    pub render_queue: Vec<RenderJob>,
+    pub download_queue: Vec<QueuedDownload>,
+    /// How many packages of the download queue are installed at the same time.
+    pub max_concurrent_downloads: usize,
+    /// Local clone of Blender's repository, where the commits between packages are read from.
+    pub blender_repository: Option<PathBuf>,
+    /// Where stable and LTS packages are downloaded from, see `mirrors::ranked`.
+    pub mirrors: Vec<Mirror>,
+    /// OS and architecture combinations that packages are fetched for, which is only the current
+    /// machine's by default.
+    pub targets: Vec<Target>,
     pub bypass_launcher: bool,
     pub modifier_key: ModifierKey,
     pub use_latest_as_default: bool,
+    /// Open files with the installed package closest to the version they were saved with,
+    /// instead of the default package, when the launcher is bypassed.
+    pub open_with_closest_package: bool,
+    /// Copy files before opening them with a package that may lose data when saving them.
+    pub back_up_risky_files: bool,
     pub check_updates_at_launch: bool,
     pub minutes_between_updates: u64,
     pub update_daily_latest: bool,
@@ -99,6 +127,7 @@ pub struct Settings {
     pub databases_dir: PathBuf,
     pub packages_dir: PathBuf,
     pub cache_dir: PathBuf,
+    pub profiles_dir: PathBuf,
     pub last_update_time: SystemTime,
     pub filters: Filters,
     pub sort_by: SortBy,
@@ -124,11 +153,17 @@ impl Settings {
             settings.databases_dir = PORTABLE_PATH.join("databases");
             settings.packages_dir = PORTABLE_PATH.join("packages");
             settings.cache_dir = PORTABLE_PATH.join("cache");
+            settings.profiles_dir = PORTABLE_PATH.join("profiles");
+        }
+
+        if settings.targets.is_empty() {
+            settings.targets.push(Target::current());
         }
 
         create_dir_all(&settings.databases_dir).unwrap();
         create_dir_all(&settings.packages_dir).unwrap();
         create_dir_all(&settings.cache_dir).unwrap();
+        create_dir_all(&settings.profiles_dir).unwrap();
 
         settings
     }
@@ -149,9 +184,20 @@ impl Default for Settings {
             bookmarks: Bookmarks::default(),
             tab: Tab::default(),
             default_package: None,
+            custom_packages: Vec::new(),
+            package_profiles: HashMap::new(),
+            association_rules: Vec::new(),
+            render_queue: Vec::new(),
+            download_queue: Vec::new(),
+            max_concurrent_downloads: 2,
+            blender_repository: None,
+            mirrors: Mirror::defaults(),
+            targets: vec![Target::current()],
             bypass_launcher: false,
             modifier_key: ModifierKey::Shift,
             use_latest_as_default: true,
+            open_with_closest_package: false,
+            back_up_risky_files: true,
             check_updates_at_launch: true,
             minutes_between_updates,
             update_daily_latest: true,
@@ -162,6 +208,7 @@ impl Default for Settings {
             databases_dir: PROJECT_DIRS.config_dir().join("databases"),
             packages_dir: PROJECT_DIRS.data_local_dir().to_path_buf(),
             cache_dir: PROJECT_DIRS.cache_dir().to_path_buf(),
+            profiles_dir: PROJECT_DIRS.config_dir().join("profiles"),
             last_update_time: SystemTime::now()
                 .checked_sub(Duration::from_secs(minutes_between_updates * 60))
                 .unwrap_or_else(SystemTime::now),
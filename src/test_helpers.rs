@@ -0,0 +1,31 @@
+//! Fixtures shared by the tests of several modules.
+
+use crate::package::{Package, Target};
+use std::{
+    env::temp_dir,
+    fs::{create_dir_all, remove_dir_all},
+    path::PathBuf,
+    process,
+};
+use versions::Versioning;
+
+/// A package for the machine the tests run on, with only a name and a version.
+pub fn package(name: &str, version: &str) -> Package {
+    let target = Target::current();
+    Package {
+        version: Versioning::new(version).unwrap(),
+        name: name.to_string(),
+        os: target.os,
+        arch: target.arch,
+        ..Package::default()
+    }
+}
+
+/// An empty directory for a test to work in, which is named after it so tests running at the
+/// same time don't step on each other.
+pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir = temp_dir().join(format!("ablavema-{}-{}", name, process::id()));
+    let _ = remove_dir_all(&dir);
+    create_dir_all(&dir).unwrap();
+    dir
+}
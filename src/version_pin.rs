@@ -0,0 +1,155 @@
+use crate::{
+    error::{AblavemaError, Result},
+    package::Package,
+    query::{PackageQuery, Resolved},
+};
+use std::{
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+/// Name of the file that pins the .blend files next to it and below it to a package.
+pub const VERSION_FILE: &str = ".blender-version";
+
+/// A project's choice of package, read from the closest version file above a .blend file.
+///
+/// The file holds a package query, like "4.2" for any 4.2 release or "lts", either by itself or
+/// as `package = 4.2`, plus an optional `profile = name`. Lines starting with '#' are comments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VersionPin {
+    pub file: PathBuf,
+    pub query: PackageQuery,
+    pub profile: Option<String>,
+}
+
+impl VersionPin {
+    /// Look for a version file in the directory of the .blend file and every one above it.
+    /// Files that can't be read are reported and skipped.
+    pub fn find(blend_file: &Path) -> Option<VersionPin> {
+        for dir in blend_file.parent()?.ancestors() {
+            let file = dir.join(VERSION_FILE);
+            if !file.is_file() {
+                continue;
+            }
+
+            match VersionPin::read(&file) {
+                Ok(pin) => return Some(pin),
+                Err(e) => eprintln!("Ignoring version file: {}", e),
+            }
+        }
+
+        None
+    }
+
+    fn read(file: &Path) -> Result<VersionPin> {
+        let text = read_to_string(file).map_err(|e| AblavemaError::io(file, e))?;
+        let mut query = None;
+        let mut profile = None;
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match line.split_once('=') {
+                Some((key, value)) => match key.trim() {
+                    "package" => query = Some(value.trim().to_string()),
+                    "profile" => profile = Some(value.trim().to_string()),
+                    key => return Err(AblavemaError::io(file, format!("unknown key '{}'", key))),
+                },
+                None if query.is_none() => query = Some(line.to_string()),
+                None => return Err(AblavemaError::io(file, format!("unexpected '{}'", line))),
+            }
+        }
+
+        match query {
+            Some(query) => Ok(VersionPin {
+                file: file.to_path_buf(),
+                query: query.parse().map_err(|e| AblavemaError::io(file, e))?,
+                profile: profile.filter(|profile| !profile.is_empty()),
+            }),
+            None => Err(AblavemaError::io(file, "no package given")),
+        }
+    }
+
+    /// The package to open the file with, out of the installed ones.
+    pub fn installed<'a>(
+        &self,
+        installed: impl IntoIterator<Item = &'a Package>,
+    ) -> Option<&'a Package> {
+        self.query.newest(installed)
+    }
+
+    /// The package to offer installing when none of the pinned ones is installed.
+    pub fn installable<'a>(
+        &self,
+        packages: impl IntoIterator<Item = &'a Package>,
+    ) -> Option<&'a Package> {
+        match self.query.resolve([], packages) {
+            Some(Resolved::Installable(package)) => Some(package),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{package, scratch_dir};
+    use std::fs::create_dir_all;
+
+    fn pin(query: &str) -> VersionPin {
+        VersionPin {
+            file: PathBuf::from(VERSION_FILE),
+            query: query.parse().unwrap(),
+            profile: None,
+        }
+    }
+
+    #[test]
+    fn find_in_parent_directories() {
+        let root = scratch_dir("pin");
+        let scenes = root.join("project").join("scenes");
+        create_dir_all(&scenes).unwrap();
+        std::fs::write(
+            root.join("project").join(VERSION_FILE),
+            "# Stay on 3.6 until the release.\npackage = 3.6\nprofile = studio\n",
+        )
+        .unwrap();
+
+        let found = VersionPin::find(&scenes.join("shot.blend")).unwrap();
+        assert_eq!(found.file, root.join("project").join(VERSION_FILE));
+        assert_eq!(found.query.to_string(), "3.6");
+        assert_eq!(found.profile.as_deref(), Some("studio"));
+
+        // The closest file wins.
+        std::fs::write(scenes.join(VERSION_FILE), "4.2.1\n").unwrap();
+        let found = VersionPin::find(&scenes.join("shot.blend")).unwrap();
+        assert_eq!(found.query.to_string(), "4.2.1");
+        assert_eq!(found.profile, None);
+
+        std::fs::write(scenes.join(VERSION_FILE), "colour = blue\n").unwrap();
+        assert!(VersionPin::read(&scenes.join(VERSION_FILE)).is_err());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn pick_packages() {
+        let packages = [
+            package("blender-3.6.2", "3.6.2"),
+            package("blender-3.6.5", "3.6.5"),
+            package("blender-4.2.1", "4.2.1"),
+            package("my-build", "4.3.0"),
+        ];
+        let installed = |query| pin(query).installed(&packages).map(|p| p.name.as_str());
+
+        assert_eq!(installed("3.6"), Some("blender-3.6.5"));
+        assert_eq!(installed("3.6.2"), Some("blender-3.6.2"));
+        assert_eq!(installed("4"), Some("my-build"));
+        assert_eq!(installed("4.2.*"), Some("blender-4.2.1"));
+        assert_eq!(installed("my-build"), Some("my-build"));
+        assert_eq!(installed("3.7"), None);
+        assert_eq!(installed("something"), None);
+    }
+}